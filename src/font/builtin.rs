@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+
+use super::{Family, Font, FontData, FontId, Source, Stretch, Style, Synthesis, Weight};
+
+/// A face compiled into the binary.
+/// The metadata is curated by hand, several of the static files misreport it
+/// (e.g. Roboto Thin calls itself family "Roboto Thin" with weight 250).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundledFace {
+    pub family: &'static str,
+    pub weight: Weight,
+    pub style: Style,
+    pub monospace: bool,
+    pub data: &'static [u8],
+}
+
+impl BundledFace {
+    const fn new(family: &'static str, weight: Weight, style: Style, monospace: bool, data: &'static [u8]) -> Self {
+        Self {
+            family,
+            weight,
+            style,
+            monospace,
+            data,
+        }
+    }
+
+    pub fn font(&self) -> Font {
+        Font {
+            family: Family::Named(Cow::Borrowed(self.family)),
+            weight: self.weight,
+            style: self.style,
+            stretch: Stretch::Normal,
+            monospace: self.monospace,
+            variations: Vec::new(),
+            src: Source::Builtin,
+            data: FontData::Static(self.data),
+            index: 0,
+            synthesis: Synthesis::NONE,
+            id: FontId::intern(&FontData::Static(self.data), 0, &[], Synthesis::NONE),
+        }
+    }
+}
+
+/// Every face enabled through the `cairo`, `roboto` and `source-code-pro` features.
+/// DejaVu Serif backs [`Font::DEFAULT`] and is always included.
+pub fn bundled() -> impl Iterator<Item = &'static BundledFace> {
+    let families: &[&[BundledFace]] = &[
+        DEJAVU_SERIF,
+        #[cfg(feature = "cairo")]
+        CAIRO,
+        #[cfg(feature = "roboto")]
+        ROBOTO,
+        #[cfg(feature = "source-code-pro")]
+        SOURCE_CODE_PRO,
+    ];
+    families.iter().flat_map(|faces| faces.iter())
+}
+
+/// The variable faces enabled through the `cairo` and `source-code-pro` features, at their default
+/// instance. Pick an instance with [`Font::with_variation`], e.g. weight 650 and slant -8 of Cairo.
+/// They aren't part of [`bundled`], matching only sees the static faces.
+pub fn bundled_variable() -> impl Iterator<Item = &'static BundledFace> {
+    let families: &[&[BundledFace]] = &[
+        #[cfg(feature = "cairo")]
+        CAIRO_VARIABLE,
+        #[cfg(feature = "source-code-pro")]
+        SOURCE_CODE_PRO_VARIABLE,
+    ];
+    families.iter().flat_map(|faces| faces.iter())
+}
+
+const DEJAVU_SERIF: &[BundledFace] = &[
+    BundledFace::new("DejaVu Serif", Weight::Normal, Style::Normal, false, include_bytes!("../fonts/DejaVuSerif.ttf")),
+];
+
+#[cfg(feature = "cairo")]
+const CAIRO: &[BundledFace] = &[
+    BundledFace::new("Cairo", Weight::ExtraLigh, Style::Normal, false, include_bytes!("../fonts/Cairo/static/Cairo-ExtraLight.ttf")),
+    BundledFace::new("Cairo", Weight::Light, Style::Normal, false, include_bytes!("../fonts/Cairo/static/Cairo-Light.ttf")),
+    BundledFace::new("Cairo", Weight::Normal, Style::Normal, false, include_bytes!("../fonts/Cairo/static/Cairo-Regular.ttf")),
+    BundledFace::new("Cairo", Weight::Medium, Style::Normal, false, include_bytes!("../fonts/Cairo/static/Cairo-Medium.ttf")),
+    BundledFace::new("Cairo", Weight::Semibold, Style::Normal, false, include_bytes!("../fonts/Cairo/static/Cairo-SemiBold.ttf")),
+    BundledFace::new("Cairo", Weight::Bold, Style::Normal, false, include_bytes!("../fonts/Cairo/static/Cairo-Bold.ttf")),
+    BundledFace::new("Cairo", Weight::ExtraBold, Style::Normal, false, include_bytes!("../fonts/Cairo/static/Cairo-ExtraBold.ttf")),
+    BundledFace::new("Cairo", Weight::Black, Style::Normal, false, include_bytes!("../fonts/Cairo/static/Cairo-Black.ttf")),
+];
+
+#[cfg(feature = "cairo")]
+const CAIRO_VARIABLE: &[BundledFace] = &[
+    BundledFace::new("Cairo", Weight::Normal, Style::Normal, false, include_bytes!("../fonts/Cairo/Cairo-VariableFont_slnt,wght.ttf")),
+];
+
+#[cfg(feature = "roboto")]
+const ROBOTO: &[BundledFace] = &[
+    BundledFace::new("Roboto", Weight::Thin, Style::Normal, false, include_bytes!("../fonts/Roboto/Roboto-Thin.ttf")),
+    BundledFace::new("Roboto", Weight::Thin, Style::Italic, false, include_bytes!("../fonts/Roboto/Roboto-ThinItalic.ttf")),
+    BundledFace::new("Roboto", Weight::Light, Style::Normal, false, include_bytes!("../fonts/Roboto/Roboto-Light.ttf")),
+    BundledFace::new("Roboto", Weight::Light, Style::Italic, false, include_bytes!("../fonts/Roboto/Roboto-LightItalic.ttf")),
+    BundledFace::new("Roboto", Weight::Normal, Style::Normal, false, include_bytes!("../fonts/Roboto/Roboto-Regular.ttf")),
+    BundledFace::new("Roboto", Weight::Normal, Style::Italic, false, include_bytes!("../fonts/Roboto/Roboto-Italic.ttf")),
+    BundledFace::new("Roboto", Weight::Medium, Style::Normal, false, include_bytes!("../fonts/Roboto/Roboto-Medium.ttf")),
+    BundledFace::new("Roboto", Weight::Medium, Style::Italic, false, include_bytes!("../fonts/Roboto/Roboto-MediumItalic.ttf")),
+    BundledFace::new("Roboto", Weight::Bold, Style::Normal, false, include_bytes!("../fonts/Roboto/Roboto-Bold.ttf")),
+    BundledFace::new("Roboto", Weight::Bold, Style::Italic, false, include_bytes!("../fonts/Roboto/Roboto-BoldItalic.ttf")),
+    BundledFace::new("Roboto", Weight::Black, Style::Normal, false, include_bytes!("../fonts/Roboto/Roboto-Black.ttf")),
+    BundledFace::new("Roboto", Weight::Black, Style::Italic, false, include_bytes!("../fonts/Roboto/Roboto-BlackItalic.ttf")),
+];
+
+#[cfg(feature = "source-code-pro")]
+const SOURCE_CODE_PRO: &[BundledFace] = &[
+    BundledFace::new("Source Code Pro", Weight::ExtraLigh, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-ExtraLight.ttf")),
+    BundledFace::new("Source Code Pro", Weight::ExtraLigh, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-ExtraLightItalic.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Light, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-Light.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Light, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-LightItalic.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Normal, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-Regular.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Normal, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-Italic.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Medium, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-Medium.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Medium, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-MediumItalic.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Semibold, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-SemiBold.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Semibold, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-SemiBoldItalic.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Bold, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-Bold.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Bold, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-BoldItalic.ttf")),
+    BundledFace::new("Source Code Pro", Weight::ExtraBold, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-ExtraBold.ttf")),
+    BundledFace::new("Source Code Pro", Weight::ExtraBold, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-ExtraBoldItalic.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Black, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-Black.ttf")),
+    BundledFace::new("Source Code Pro", Weight::Black, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-BlackItalic.ttf")),
+];
+
+#[cfg(feature = "source-code-pro")]
+const SOURCE_CODE_PRO_VARIABLE: &[BundledFace] = &[
+    BundledFace::new("Source Code Pro", Weight::ExtraLigh, Style::Normal, true, include_bytes!("../fonts/Source_Code_Pro/SourceCodePro-VariableFont_wght.ttf")),
+    BundledFace::new("Source Code Pro", Weight::ExtraLigh, Style::Italic, true, include_bytes!("../fonts/Source_Code_Pro/SourceCodePro-Italic-VariableFont_wght.ttf")),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Variation;
+
+    #[test]
+    fn variable_faces_are_described_by_their_default_instance() {
+        for face in bundled_variable() {
+            let axes = face.font().variation_axes();
+            let weight = axes.iter().find(|axis| axis.axis == Variation::WEIGHT).unwrap();
+            assert_eq!(Weight::from(weight.default as u16), face.weight, "{} {:?}", face.family, face.style);
+        }
+    }
+
+    #[cfg(feature = "cairo")]
+    #[test]
+    fn cairo_takes_any_weight_and_slant() {
+        let face = bundled_variable().find(|face| face.family == "Cairo").unwrap();
+        let font = face
+            .font()
+            .with_variation(Variation::new(Variation::WEIGHT, 650.0))
+            .with_variation(Variation::new(Variation::SLANT, -8.0));
+        // The descriptor rounds to the nearest named weight.
+        assert_eq!(font.weight, Weight::Bold);
+        assert_eq!(font.style, Style::Oblique);
+        assert_ne!(font.id(), face.font().id());
+
+        // Going back upright drops the oblique style again.
+        let upright = font.with_variation(Variation::new(Variation::SLANT, 0.0));
+        assert_eq!(upright.style, Style::Normal);
+        let mut italic = face.font();
+        italic.style = Style::Italic;
+        let italic = italic
+            .with_variation(Variation::new(Variation::SLANT, -8.0))
+            .with_variation(Variation::new(Variation::SLANT, 0.0));
+        assert_eq!(italic.style, Style::Italic);
+    }
+}
@@ -0,0 +1,84 @@
+use ttf_parser::{GlyphId, RasterGlyphImage, RasterImageFormat, Tag};
+
+/// One layer of a COLR glyph, layers are drawn bottom to top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ColorLayer {
+    pub glyph_id: GlyphId,
+    /// Straight RGBA from the first CPAL palette, `None` means the text color.
+    pub color: Option<[u8; 4]>,
+}
+
+/// Whether `glyph_id` is drawn from color layers or an embedded color bitmap instead of its outline.
+pub(crate) fn is_color_glyph(face: &ttf_parser::Face, glyph_id: GlyphId) -> bool {
+    layers(face, glyph_id).is_some() || raster_image(face, glyph_id, u16::MAX).is_some()
+}
+
+/// The color bitmap (CBDT/sbix) of a glyph from the strike closest to `pixels_per_em`.
+/// Monochrome and grayscale strikes are skipped, those glyphs are rendered from their outline.
+pub(crate) fn raster_image<'a>(face: &'a ttf_parser::Face, glyph_id: GlyphId, pixels_per_em: u16) -> Option<RasterGlyphImage<'a>> {
+    face.glyph_raster_image(glyph_id, pixels_per_em)
+        .filter(|image| matches!(image.format, RasterImageFormat::PNG | RasterImageFormat::BitmapPremulBgra32))
+}
+
+// ttf-parser doesn't read COLR/CPAL yet, the version 0 layer records are parsed by hand.
+// Glyphs that only have a version 1 paint graph are rendered from their outline instead.
+pub(crate) fn layers(face: &ttf_parser::Face, glyph_id: GlyphId) -> Option<Vec<ColorLayer>> {
+    let colr = face.raw_face().table(Tag::from_bytes(b"COLR"))?;
+    let base_count = read_u16(colr, 2)? as usize;
+    let base_offset = read_u32(colr, 4)? as usize;
+    let layer_offset = read_u32(colr, 8)? as usize;
+    let layer_count = read_u16(colr, 12)? as usize;
+
+    // Base glyph records are sorted by glyph id.
+    let (mut low, mut high) = (0, base_count);
+    let (first, count) = loop {
+        if low >= high {
+            return None;
+        }
+        let middle = (low + high) / 2;
+        let record = base_offset + middle * 6;
+        match read_u16(colr, record)?.cmp(&glyph_id.0) {
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+            std::cmp::Ordering::Equal => break (read_u16(colr, record + 2)? as usize, read_u16(colr, record + 4)? as usize),
+        }
+    };
+    if first + count > layer_count {
+        return None;
+    }
+
+    let palette = face.raw_face().table(Tag::from_bytes(b"CPAL"));
+    (first..first + count)
+        .map(|index| {
+            let record = layer_offset + index * 4;
+            let glyph_id = GlyphId(read_u16(colr, record)?);
+            let color = match read_u16(colr, record + 2)? {
+                0xFFFF => None,
+                entry => palette.and_then(|palette| palette_color(palette, entry)),
+            };
+            Some(ColorLayer { glyph_id, color })
+        })
+        .collect()
+}
+
+// Looks `entry` up in the first palette, CPAL stores colors as BGRA.
+fn palette_color(cpal: &[u8], entry: u16) -> Option<[u8; 4]> {
+    if entry >= read_u16(cpal, 2)? {
+        return None;
+    }
+    let records_offset = read_u32(cpal, 8)? as usize;
+    let first = read_u16(cpal, 12)? as usize;
+    let offset = records_offset + (first + entry as usize) * 4;
+    let [b, g, r, a] = cpal.get(offset..offset + 4)?.try_into().ok()?;
+    Some([r, g, b, a])
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
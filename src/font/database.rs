@@ -0,0 +1,245 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use super::{builtin, matching, woff, Error, Family, Font, FontData, Query, Source, Stretch, Style, Synthesis, Weight};
+
+/// A face known to the database, described by the metadata in its name and OS/2 tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaceInfo {
+    pub family: String,
+    pub weight: Weight,
+    pub style: Style,
+    pub stretch: Stretch,
+    pub monospace: bool,
+    pub src: Source,
+    /// The face within a collection file.
+    pub index: u32,
+    // Only set for faces that live in memory, files are read again on load.
+    pub(super) data: Option<FontData>,
+}
+
+impl FaceInfo {
+    /// Loads the face, keeping the descriptor it was matched by rather than re-reading it from the file.
+    pub fn load(&self) -> Result<Font, Error> {
+        let mut font = match (&self.data, &self.src) {
+            (Some(data), src) => Font::from_data_with_index(data.clone(), self.index, src.clone())?,
+            (None, Source::Path(path)) => Font::from_path_with_index(path, self.index)?,
+            (None, src) => {
+                let msg = format!("face from {src:?} has neither data nor a file to load");
+                return Err(Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, msg)));
+            }
+        };
+        font.family = Family::Named(Cow::Owned(self.family.clone()));
+        font.weight = self.weight;
+        font.style = self.style;
+        font.stretch = self.stretch;
+        font.monospace = self.monospace;
+        Ok(font)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FontDatabase {
+    faces: Vec<FaceInfo>,
+}
+
+impl FontDatabase {
+    const EXTENSIONS: [&'static str; 6] = ["ttf", "otf", "ttc", "otc", "woff", "woff2"];
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a database populated with the bundled fonts and the fonts installed on the system.
+    pub fn system() -> Self {
+        let mut db = Self::new();
+        db.load_builtin_fonts();
+        db.load_system_fonts();
+        db
+    }
+
+    /// Adds the faces of [`bundled`](super::bundled), using their curated metadata.
+    pub fn load_builtin_fonts(&mut self) {
+        self.faces.extend(builtin::bundled().map(|face| FaceInfo {
+            family: face.family.to_string(),
+            weight: face.weight,
+            style: face.style,
+            stretch: Stretch::Normal,
+            monospace: face.monospace,
+            src: Source::Builtin,
+            index: 0,
+            data: Some(face.data.into()),
+        }));
+    }
+
+    pub fn load_system_fonts(&mut self) {
+        for dir in Self::system_font_dirs() {
+            self.load_fonts_dir(dir);
+        }
+    }
+
+    /// Recursively scans `dir` for font files, unreadable entries are skipped.
+    pub fn load_fonts_dir(&mut self, dir: impl AsRef<Path>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(ty) if ty.is_dir() => self.load_fonts_dir(&path),
+                Ok(_) if Self::is_font_file(&path) => {
+                    if let Err(err) = self.load_font_file(&path) {
+                        log::debug!("Skipping font {path:?}: {err}");
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Adds every face of a font file, collections (.ttc/.otc) contribute one entry per face.
+    /// WOFF/WOFF2 files are decoded once here and again when the face is loaded.
+    pub fn load_font_file(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let data = woff::decode(std::fs::read(path)?.into())?;
+        for index in 0..Font::face_count(&data) {
+            let face = ttf_parser::Face::parse(&data, index)?;
+            let family = Font::family_name(&face).unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+            self.push(&face, index, family, Source::Path(path.to_path_buf()), None);
+        }
+        Ok(())
+    }
+
+    /// Adds a font that is already in memory, e.g. one compiled in with `include_bytes!`.
+    pub fn load_font_data(&mut self, data: impl Into<FontData>, src: Source) -> Result<(), Error> {
+        let data = woff::decode(data.into())?;
+        for index in 0..Font::face_count(&data) {
+            let face = ttf_parser::Face::parse(&data, index)?;
+            let family = Font::family_name(&face).unwrap_or_default();
+            self.push(&face, index, family, src.clone(), Some(data.clone()));
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, face: &ttf_parser::Face, index: u32, family: String, src: Source, data: Option<FontData>) {
+        self.faces.push(FaceInfo {
+            family,
+            weight: Weight::from(face.weight().to_number()),
+            style: face.style().into(),
+            stretch: face.width().into(),
+            monospace: face.is_monospaced(),
+            src,
+            index,
+            data,
+        });
+    }
+
+    pub fn faces(&self) -> &[FaceInfo] {
+        &self.faces
+    }
+
+    /// Finds the face best matching `query`, see [`Query`].
+    pub fn find(&self, query: &Query) -> Option<&FaceInfo> {
+        matching::select(&self.faces, query)
+    }
+
+    /// Like [`FontDatabase::find`] but loads the face.
+    /// Bold and italic that the face lacks are flagged for synthesis, see [`Synthesis`].
+    pub fn query(&self, query: &Query) -> Option<Font> {
+        let face = self.find(query)?;
+        face.load()
+            .map(|font| font.with_synthesis(Synthesis::resolve(query, face)))
+            .map_err(|err| log::warn!("Failed to load font {:?}: {err}", face.src))
+            .ok()
+    }
+
+    fn is_font_file(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| Self::EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+            .unwrap_or(false)
+    }
+
+    fn system_font_dirs() -> Vec<PathBuf> {
+        let mut dirs = vec![
+            PathBuf::from("/usr/share/fonts"),
+            PathBuf::from("/usr/local/share/fonts"),
+        ];
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        match std::env::var_os("XDG_DATA_HOME") {
+            Some(data) => dirs.push(PathBuf::from(data).join("fonts")),
+            None => dirs.extend(home.as_ref().map(|home| home.join(".local/share/fonts"))),
+        }
+        dirs.extend(home.map(|home| home.join(".fonts")));
+        dirs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTO_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/fonts/Roboto");
+    const BOLD_ITALIC: &[u8] = include_bytes!("../fonts/Roboto/Roboto-BoldItalic.ttf");
+
+    fn bold_italic() -> Query {
+        Query {
+            families: vec![Family::Named("Roboto".into())],
+            weight: Weight::Bold,
+            style: Style::Italic,
+            ..Query::default()
+        }
+    }
+
+    #[test]
+    fn finds_the_bold_italic_file_in_a_dir() {
+        let mut db = FontDatabase::new();
+        db.load_fonts_dir(ROBOTO_DIR);
+        // 12 static faces and Regular again as WOFF and WOFF2, the license is skipped.
+        assert_eq!(db.faces().len(), 14);
+        let face = db.find(&bold_italic()).unwrap();
+        assert_eq!(face.src, Source::Path(Path::new(ROBOTO_DIR).join("Roboto-BoldItalic.ttf")));
+        let font = db.query(&bold_italic()).unwrap();
+        assert_eq!((font.weight, font.style), (Weight::Bold, Style::Italic));
+        assert_eq!(font.synthesis(), Synthesis::NONE);
+        assert_eq!(&*font.data, BOLD_ITALIC);
+    }
+
+    #[test]
+    fn finds_the_bold_italic_face_in_memory() {
+        let mut db = FontDatabase::new();
+        for data in [
+            include_bytes!("../fonts/Roboto/Roboto-Regular.ttf").as_slice(),
+            include_bytes!("../fonts/Roboto/Roboto-Bold.ttf"),
+            include_bytes!("../fonts/Roboto/Roboto-Italic.ttf"),
+            BOLD_ITALIC,
+        ] {
+            db.load_font_data(data, Source::Builtin).unwrap();
+        }
+        let font = db.query(&bold_italic()).unwrap();
+        assert_eq!(font.family, Family::Named("Roboto".into()));
+        assert_eq!((font.weight, font.style), (Weight::Bold, Style::Italic));
+        assert_eq!(&*font.data, BOLD_ITALIC);
+    }
+
+    #[test]
+    fn faces_without_data_or_file_fail_to_load() {
+        let face = FaceInfo {
+            family: "Roboto".to_string(),
+            weight: Weight::Normal,
+            style: Style::Normal,
+            stretch: Stretch::Normal,
+            monospace: false,
+            src: Source::Builtin,
+            index: 0,
+            data: None,
+        };
+        assert!(matches!(face.load(), Err(Error::Io(_))));
+    }
+}
@@ -0,0 +1,169 @@
+use std::ops::Range;
+
+/// An OpenType feature setting, e.g. `tnum=1` or `liga=0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Feature {
+    pub tag: [u8; 4],
+    /// 0 disables the feature, 1 enables it, larger values pick an alternate (e.g. for `cv01`).
+    pub value: u32,
+    /// The byte range of the text the setting applies to, `None` for all of it.
+    pub range: Option<Range<usize>>,
+}
+
+impl Feature {
+    pub const KERNING: [u8; 4] = *b"kern";
+    pub const STANDARD_LIGATURES: [u8; 4] = *b"liga";
+    pub const CONTEXTUAL_LIGATURES: [u8; 4] = *b"clig";
+    pub const DISCRETIONARY_LIGATURES: [u8; 4] = *b"dlig";
+    pub const SMALL_CAPS: [u8; 4] = *b"smcp";
+    pub const TABULAR_NUMBERS: [u8; 4] = *b"tnum";
+    pub const OLDSTYLE_NUMBERS: [u8; 4] = *b"onum";
+
+    pub fn new(tag: [u8; 4], value: u32) -> Self {
+        Self { tag, value, range: None }
+    }
+
+    /// The tag of stylistic set `ss01` to `ss20`.
+    ///
+    /// # Panics
+    ///
+    /// If `set` isn't in `1..=20`.
+    pub fn stylistic_set(set: u8) -> [u8; 4] {
+        assert!((1..=20).contains(&set), "stylistic sets range from 1 to 20, got {set}");
+        [b's', b's', b'0' + set / 10, b'0' + set % 10]
+    }
+
+    /// The tag of character variant `cv01` to `cv99`.
+    ///
+    /// # Panics
+    ///
+    /// If `variant` isn't in `1..=99`.
+    pub fn character_variant(variant: u8) -> [u8; 4] {
+        assert!((1..=99).contains(&variant), "character variants range from 1 to 99, got {variant}");
+        [b'c', b'v', b'0' + variant / 10, b'0' + variant % 10]
+    }
+}
+
+/// The OpenType features to shape a piece of text with. Features that are not set keep the
+/// shaper's defaults, so kerning and standard ligatures stay on unless they are disabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FontFeatures {
+    features: Vec<Feature>,
+}
+
+impl FontFeatures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Later settings win over earlier ones where their ranges overlap.
+    pub fn with_feature(mut self, feature: Feature) -> Self {
+        self.features.push(feature);
+        self
+    }
+
+    pub fn with(self, tag: [u8; 4], value: u32) -> Self {
+        self.with_feature(Feature::new(tag, value))
+    }
+
+    /// Like [`FontFeatures::with`] but only for the bytes in `range`.
+    pub fn with_range(self, tag: [u8; 4], value: u32, range: Range<usize>) -> Self {
+        self.with_feature(Feature {
+            tag,
+            value,
+            range: Some(range),
+        })
+    }
+
+    pub fn with_kerning(self, enabled: bool) -> Self {
+        self.with(Feature::KERNING, enabled as u32)
+    }
+
+    /// Toggles both standard and contextual ligatures, e.g. to keep `->` or `!=` apart in code.
+    pub fn with_ligatures(self, enabled: bool) -> Self {
+        self.with(Feature::STANDARD_LIGATURES, enabled as u32)
+            .with(Feature::CONTEXTUAL_LIGATURES, enabled as u32)
+    }
+
+    pub fn with_discretionary_ligatures(self, enabled: bool) -> Self {
+        self.with(Feature::DISCRETIONARY_LIGATURES, enabled as u32)
+    }
+
+    pub fn with_small_caps(self, enabled: bool) -> Self {
+        self.with(Feature::SMALL_CAPS, enabled as u32)
+    }
+
+    /// Gives every digit the same advance so numbers line up in columns.
+    pub fn with_tabular_numbers(self, enabled: bool) -> Self {
+        self.with(Feature::TABULAR_NUMBERS, enabled as u32)
+    }
+
+    pub fn with_oldstyle_numbers(self, enabled: bool) -> Self {
+        self.with(Feature::OLDSTYLE_NUMBERS, enabled as u32)
+    }
+
+    /// # Panics
+    ///
+    /// If `set` isn't in `1..=20`, see [`Feature::stylistic_set`].
+    pub fn with_stylistic_set(self, set: u8, enabled: bool) -> Self {
+        self.with(Feature::stylistic_set(set), enabled as u32)
+    }
+
+    /// Picks alternate `value` of character variant `variant`, 0 restores the default glyphs.
+    ///
+    /// # Panics
+    ///
+    /// If `variant` isn't in `1..=99`, see [`Feature::character_variant`].
+    pub fn with_character_variant(self, variant: u8, value: u32) -> Self {
+        self.with(Feature::character_variant(variant), value)
+    }
+
+    pub fn features(&self) -> &[Feature] {
+        &self.features
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_tags_are_zero_padded() {
+        assert_eq!(Feature::stylistic_set(1), *b"ss01");
+        assert_eq!(Feature::stylistic_set(20), *b"ss20");
+        assert_eq!(Feature::character_variant(7), *b"cv07");
+        assert_eq!(Feature::character_variant(99), *b"cv99");
+    }
+
+    #[test]
+    #[should_panic(expected = "stylistic sets range from 1 to 20")]
+    fn stylistic_sets_stop_at_20() {
+        Feature::stylistic_set(21);
+    }
+
+    #[test]
+    #[should_panic(expected = "character variants range from 1 to 99")]
+    fn character_variants_start_at_1() {
+        Feature::character_variant(0);
+    }
+
+    #[test]
+    fn builders_append_tag_and_value() {
+        let features = FontFeatures::new()
+            .with_ligatures(false)
+            .with_stylistic_set(3, true)
+            .with_character_variant(12, 2)
+            .with_range(Feature::SMALL_CAPS, 1, 4..9);
+        assert_eq!(features.features(), [
+            Feature::new(*b"liga", 0),
+            Feature::new(*b"clig", 0),
+            Feature::new(*b"ss03", 1),
+            Feature::new(*b"cv12", 2),
+            Feature { tag: *b"smcp", value: 1, range: Some(4..9) },
+        ]);
+    }
+}
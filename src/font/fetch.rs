@@ -0,0 +1,240 @@
+use std::{
+    fmt,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+
+use super::{Error, Font, Source};
+
+pub type FetchFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, Error>> + Send + 'a>>;
+
+/// Resolves a [`Source::Url`] to the raw bytes of a font file.
+pub trait FontFetcher: Send + Sync {
+    fn fetch<'a>(&'a self, url: &'a str) -> FetchFuture<'a>;
+}
+
+/// The default fetcher, it only understands `file://` URLs and plain paths.
+/// Register a custom [`FontFetcher`] to resolve anything else (e.g. HTTP).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileFetcher;
+
+impl FontFetcher for FileFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> FetchFuture<'a> {
+        Box::pin(async move {
+            let path = match url.split_once("://") {
+                Some(("file", path)) => path,
+                Some(_) => return Err(Error::UnsupportedUrl(url.to_string())),
+                None => url,
+            };
+            Ok(async_std::fs::read(path).await?)
+        })
+    }
+}
+
+/// Loads fonts from any [`Source`], caching fonts fetched from remote URLs on disk.
+#[derive(Clone)]
+pub struct FontLoader {
+    fetcher: Arc<dyn FontFetcher>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl FontLoader {
+    pub fn new() -> Self {
+        Self {
+            fetcher: Arc::new(FileFetcher),
+            cache_dir: Self::default_cache_dir(),
+        }
+    }
+
+    pub fn with_fetcher(mut self, fetcher: impl FontFetcher + 'static) -> Self {
+        self.fetcher = Arc::new(fetcher);
+        self
+    }
+
+    /// Sets the directory fetched fonts are cached in, `None` disables the cache.
+    pub fn with_cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+        self.cache_dir = cache_dir;
+        self
+    }
+
+    pub fn cache_dir(&self) -> Option<&Path> {
+        self.cache_dir.as_deref()
+    }
+
+    pub async fn load(&self, src: &Source) -> Result<Font, Error> {
+        match src {
+            Source::Builtin => Ok(Font::DEFAULT),
+            Source::Path(path) => {
+                let data = async_std::fs::read(path).await?;
+                Font::from_data(data, src.clone())
+            }
+            Source::Url(url) => self.load_url(url, src).await,
+        }
+    }
+
+    // A cached copy that no longer parses is thrown away and fetched again.
+    async fn load_url(&self, url: &str, src: &Source) -> Result<Font, Error> {
+        let cached = self.cache_path(url);
+
+        if let Some(path) = &cached {
+            if let Ok(data) = async_std::fs::read(path).await {
+                match Font::from_data(data, src.clone()) {
+                    Ok(font) => return Ok(font),
+                    Err(err) => {
+                        log::warn!("Discarding cached font {url} at {path:?}: {err}");
+                        if let Err(err) = async_std::fs::remove_file(path).await {
+                            log::warn!("Failed to remove cached font {path:?}: {err}");
+                        }
+                    }
+                }
+            }
+        }
+
+        let data = self.fetcher.fetch(url).await?;
+        let font = Font::from_data(data.clone(), src.clone())?;
+
+        if let Some(path) = &cached {
+            let write = async {
+                if let Some(dir) = path.parent() {
+                    async_std::fs::create_dir_all(dir).await?;
+                }
+                async_std::fs::write(path, &data).await
+            };
+            if let Err(err) = write.await {
+                log::warn!("Failed to cache font {url} at {path:?}: {err}");
+            }
+        }
+
+        Ok(font)
+    }
+
+    // Local files are read directly, caching them would only serve stale copies.
+    fn cache_path(&self, url: &str) -> Option<PathBuf> {
+        match url.split_once("://") {
+            Some((scheme, _)) if scheme != "file" => self
+                .cache_dir
+                .as_ref()
+                .map(|dir| dir.join(format!("{:016x}.font", Self::cache_key(url)))),
+            _ => None,
+        }
+    }
+
+    // FNV-1a, the key has to stay stable between builds so the on-disk cache survives.
+    fn cache_key(url: &str) -> u64 {
+        url.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    fn default_cache_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .map(|dir| dir.join("framer").join("fonts"))
+    }
+}
+
+impl Default for FontLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for FontLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FontLoader")
+            .field("cache_dir", &self.cache_dir)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    const ROBOTO: &[u8] = include_bytes!("../fonts/Roboto/Roboto-Regular.ttf");
+
+    // Serves fonts from memory and counts the requests.
+    #[derive(Default)]
+    struct MemoryFetcher {
+        fonts: HashMap<&'static str, &'static [u8]>,
+        fetches: Arc<AtomicUsize>,
+    }
+
+    impl FontFetcher for MemoryFetcher {
+        fn fetch<'a>(&'a self, url: &'a str) -> FetchFuture<'a> {
+            Box::pin(async move {
+                self.fetches.fetch_add(1, Ordering::SeqCst);
+                self.fonts
+                    .get(url)
+                    .map(|data| data.to_vec())
+                    .ok_or_else(|| Error::UnsupportedUrl(url.to_string()))
+            })
+        }
+    }
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("framer-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn memory_loader(dir: &Path) -> (FontLoader, Arc<AtomicUsize>) {
+        let fetcher = MemoryFetcher {
+            fonts: HashMap::from([("https://fonts.test/roboto.ttf", ROBOTO)]),
+            ..Default::default()
+        };
+        let fetches = fetcher.fetches.clone();
+        let loader = FontLoader::new()
+            .with_fetcher(fetcher)
+            .with_cache_dir(Some(dir.to_path_buf()));
+        (loader, fetches)
+    }
+
+    #[async_std::test]
+    async fn second_load_is_served_from_cache() {
+        let dir = cache_dir("cache");
+        let src = Source::Url("https://fonts.test/roboto.ttf");
+        let (loader, fetches) = memory_loader(&dir);
+        let first = loader.load(&src).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        let (loader, fetches) = memory_loader(&dir);
+        let second = loader.load(&src).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 0);
+        assert_eq!(first, second);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[async_std::test]
+    async fn corrupt_cache_entries_are_fetched_again() {
+        let dir = cache_dir("corrupt");
+        let src = Source::Url("https://fonts.test/roboto.ttf");
+        let (loader, fetches) = memory_loader(&dir);
+        let path = loader.cache_path("https://fonts.test/roboto.ttf").unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, b"not a font").unwrap();
+        loader.load(&src).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(std::fs::read(&path).unwrap(), ROBOTO);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[async_std::test]
+    async fn local_files_are_not_cached() {
+        let dir = cache_dir("local");
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/fonts/Roboto/Roboto-Regular.ttf");
+        let url = concat!("file://", env!("CARGO_MANIFEST_DIR"), "/src/fonts/Roboto/Roboto-Regular.ttf");
+        let loader = FontLoader::new().with_cache_dir(Some(dir.clone()));
+        assert_eq!(loader.cache_path(url), None);
+        assert_eq!(loader.cache_path(path), None);
+        loader.load(&Source::Url(url)).await.unwrap();
+        assert!(!dir.exists());
+    }
+}
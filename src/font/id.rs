@@ -0,0 +1,276 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex, Weak},
+};
+
+use lazy_static::lazy_static;
+
+use super::{Font, FontData, Synthesis, Variation};
+
+/// A cheap handle for a font, equal for fonts that render the same glyphs:
+/// same face bytes, face index, variations and synthesis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FontId(u32);
+
+#[derive(PartialEq, Eq, Hash)]
+struct Key {
+    content: u32,
+    index: u32,
+    variations: Vec<Variation>,
+    synthesis: Synthesis,
+}
+
+// A buffer the interner has seen. Shared buffers are held weakly so that their address isn't
+// reused while the entry exists. A weak reference still keeps the allocation alive, so the last
+// font holding a buffer removes its entry when it's dropped, see `Interner::forget`.
+enum Buffer {
+    Static(&'static [u8]),
+    Shared(Weak<[u8]>),
+}
+
+impl Buffer {
+    fn new(data: &FontData) -> Self {
+        match data {
+            FontData::Static(data) => Self::Static(data),
+            FontData::Shared(data) => Self::Shared(Arc::downgrade(data)),
+        }
+    }
+
+    fn get(&self) -> Option<FontData> {
+        match self {
+            Self::Static(data) => Some(FontData::Static(data)),
+            Self::Shared(data) => data.upgrade().map(FontData::Shared),
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        match self {
+            Self::Static(_) => true,
+            Self::Shared(data) => data.strong_count() > 0,
+        }
+    }
+}
+
+struct Entry {
+    buffer: Buffer,
+    hash: u64,
+    content: u32,
+}
+
+#[derive(Default)]
+struct Interner {
+    // Content by buffer address, so a buffer is only hashed the first time it is seen. Buffers with
+    // equal hashes are compared byte for byte so a collision can't merge two fonts.
+    addresses: HashMap<(usize, usize), Entry>,
+    contents: u32,
+    ids: HashMap<Key, FontId>,
+    // Ids are never reused, caches keyed by the id of a dropped font can't mistake another font for it.
+    interned: u32,
+}
+
+lazy_static! {
+    static ref INTERNER: Mutex<Interner> = Mutex::new(Interner::with_constants());
+}
+
+impl FontId {
+    // Ids of the `Font` constants, which can't intern themselves.
+    pub(super) const DEFAULT: Self = Self(0);
+    #[cfg(feature = "source-code-pro")]
+    pub(super) const MONOSPACE: Self = Self(1);
+    #[cfg(feature = "cairo")]
+    pub(super) const CAIRO: Self = Self(2);
+    const CONSTANTS: u32 = 3;
+
+    /// Interns the fields that make up a font's identity, the bytes are hashed the first time
+    /// a buffer is seen.
+    pub(super) fn intern(data: &FontData, index: u32, variations: &[Variation], synthesis: Synthesis) -> Self {
+        INTERNER.lock().unwrap().id(data, index, variations, synthesis)
+    }
+}
+
+impl Interner {
+    fn with_constants() -> Self {
+        let mut interner = Self::default();
+        let constants = [
+            (Font::DEFAULT, FontId::DEFAULT),
+            #[cfg(feature = "source-code-pro")]
+            (Font::MONOSPACE, FontId::MONOSPACE),
+            #[cfg(feature = "cairo")]
+            (Font::CAIRO, FontId::CAIRO),
+        ];
+        for (font, id) in constants {
+            let key = Key {
+                content: interner.content(&font.data),
+                index: font.index,
+                variations: font.variations.clone(),
+                synthesis: font.synthesis,
+            };
+            interner.ids.insert(key, id);
+        }
+        interner
+    }
+
+    fn id(&mut self, data: &FontData, index: u32, variations: &[Variation], synthesis: Synthesis) -> FontId {
+        let key = Key {
+            content: self.content(data),
+            index,
+            variations: variations.to_vec(),
+            synthesis,
+        };
+        let next = FontId(FontId::CONSTANTS + self.interned);
+        let id = *self.ids.entry(key).or_insert(next);
+        if id == next {
+            self.interned += 1;
+        }
+        id
+    }
+
+    fn content(&mut self, data: &FontData) -> u32 {
+        let address = (data.as_ptr() as usize, data.len());
+        if let Some(entry) = self.addresses.get(&address) {
+            return entry.content;
+        }
+        self.prune();
+
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let hash = hasher.finish();
+        let content = match self
+            .addresses
+            .values()
+            .find(|entry| entry.hash == hash && entry.buffer.get().is_some_and(|other| other == *data))
+        {
+            Some(entry) => entry.content,
+            None => {
+                self.contents += 1;
+                self.contents - 1
+            }
+        };
+        let buffer = Buffer::new(data);
+        self.addresses.insert(address, Entry { buffer, hash, content });
+        content
+    }
+
+    // Drops the entries of buffers no font holds anymore, and the ids made from them.
+    fn prune(&mut self) {
+        self.addresses.retain(|_, entry| entry.buffer.is_alive());
+        let contents = self.addresses.values().map(|entry| entry.content).collect::<HashSet<_>>();
+        self.ids.retain(|key, _| contents.contains(&key.content));
+    }
+
+    // Called by the last font holding `data` right before the buffer is freed.
+    fn forget(&mut self, data: &Arc<[u8]>) {
+        self.addresses.remove(&(data.as_ptr() as usize, data.len()));
+        self.prune();
+    }
+}
+
+impl Font {
+    /// Interned when the font is created or its identity changes, reading it is free.
+    pub fn id(&self) -> FontId {
+        self.id
+    }
+}
+
+impl Drop for Font {
+    fn drop(&mut self) {
+        // Buffers that outlive their last font, e.g. in a `FontDatabase`, are pruned on the next miss.
+        if let FontData::Shared(data) = &self.data {
+            if Arc::strong_count(data) == 1 {
+                if let Ok(mut interner) = INTERNER.lock() {
+                    interner.forget(data);
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq for Font {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for Font {}
+
+impl Hash for Font {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTO: &[u8] = include_bytes!("../fonts/Roboto/Roboto-Regular.ttf");
+
+    #[test]
+    fn equal_bytes_in_different_buffers_share_an_id() {
+        let a = Font::from_data(ROBOTO.to_vec(), Default::default()).unwrap();
+        let b = Font::from_data(ROBOTO.to_vec(), Default::default()).unwrap();
+        assert_eq!(a.id(), b.id());
+        assert_eq!(a, b);
+        assert_ne!(a.id(), Font::DEFAULT.id());
+    }
+
+    #[test]
+    fn builders_change_the_id() {
+        let font = Font::from_data(ROBOTO, Default::default()).unwrap();
+        let bold = font.clone().with_synthesis(Synthesis { bold: true, ..Synthesis::NONE });
+        assert_ne!(font.id(), bold.id());
+        assert_eq!(bold.with_synthesis(Synthesis::NONE).id(), font.id());
+    }
+
+    #[test]
+    fn constants_match_their_interned_id() {
+        let font = Font::from_data(Font::DEFAULT.data.clone(), Default::default()).unwrap();
+        assert_eq!(font.id(), FontId::DEFAULT);
+    }
+
+    // Counts the bytes fed to it.
+    #[derive(Default)]
+    struct Counter(usize);
+
+    impl Hasher for Counter {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0 += bytes.len();
+        }
+    }
+
+    #[test]
+    fn comparing_fonts_never_reads_the_bytes() {
+        let font = Font::from_data(ROBOTO.to_vec(), Default::default()).unwrap();
+        let mut counter = Counter::default();
+        font.hash(&mut counter);
+        assert_eq!(counter.0, std::mem::size_of::<FontId>());
+        // Equal ids make equal fonts whatever the bytes.
+        let mut other = font.clone();
+        other.data = FontData::default();
+        assert_eq!(font, other);
+    }
+
+    #[test]
+    fn dropping_the_last_font_forgets_its_buffer() {
+        // A face no other test loads, so no other buffer keeps its content alive.
+        let data: Arc<[u8]> = include_bytes!("../fonts/Roboto/Roboto-ThinItalic.ttf").as_slice().into();
+        let address = (data.as_ptr() as usize, data.len());
+        let font = Font::from_data(data, Default::default()).unwrap();
+        let bold = font.clone().with_synthesis(Synthesis { bold: true, ..Synthesis::NONE });
+        let ids = [font.id(), bold.id()];
+        let interned = |interner: &Interner| {
+            let ids = ids.map(|id| interner.ids.values().any(|other| *other == id));
+            (interner.addresses.contains_key(&address), ids)
+        };
+        assert_eq!(interned(&INTERNER.lock().unwrap()), (true, [true, true]));
+        drop(font);
+        assert_eq!(interned(&INTERNER.lock().unwrap()), (true, [true, true]));
+        drop(bold);
+        assert_eq!(interned(&INTERNER.lock().unwrap()), (false, [false, false]));
+    }
+}
@@ -0,0 +1,242 @@
+use super::{FaceInfo, Family, Stretch, Style, Weight};
+
+/// Describes the face a piece of text wants, resolved with the CSS font matching algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    /// Tried in order, generic families expand to a list of well known family names.
+    pub families: Vec<Family>,
+    pub weight: Weight,
+    pub style: Style,
+    pub stretch: Stretch,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self {
+            families: vec![Family::SansSerif],
+            weight: Weight::Normal,
+            style: Style::Normal,
+            stretch: Stretch::Normal,
+        }
+    }
+}
+
+impl Family {
+    const SERIF: &'static [&'static str] = &["DejaVu Serif", "Noto Serif", "Liberation Serif", "Times New Roman"];
+    const SANS_SERIF: &'static [&'static str] = &["Roboto", "DejaVu Sans", "Noto Sans", "Liberation Sans", "Arial"];
+    const CURSIVE: &'static [&'static str] = &["Comic Neue", "Comic Sans MS", "URW Chancery L"];
+    const MONOSPACE: &'static [&'static str] = &["Source Code Pro", "DejaVu Sans Mono", "Noto Sans Mono", "Liberation Mono"];
+
+    /// The family names this family stands for, in order of preference.
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Self::Named(name) => vec![name.as_ref()],
+            Self::Serif => Self::SERIF.to_vec(),
+            Self::SansSerif => Self::SANS_SERIF.to_vec(),
+            Self::Cursive => Self::CURSIVE.to_vec(),
+            Self::Monospace => Self::MONOSPACE.to_vec(),
+        }
+    }
+}
+
+/// Picks the best face for `query` following CSS Fonts Level 4, §5.2.
+pub(crate) fn select<'a>(faces: &'a [FaceInfo], query: &Query) -> Option<&'a FaceInfo> {
+    query
+        .families
+        .iter()
+        .flat_map(|family| family.names())
+        .find_map(|name| {
+            let candidates = faces
+                .iter()
+                .filter(|face| face.family.eq_ignore_ascii_case(name))
+                .collect::<Vec<_>>();
+            select_in_family(&candidates, query)
+        })
+        .or_else(|| {
+            // None of the well known names exist, a monospace request can still be served by any monospaced face.
+            if !query.families.contains(&Family::Monospace) {
+                return None;
+            }
+            let monospace = faces.iter().filter(|face| face.monospace).collect::<Vec<_>>();
+            select_in_family(&monospace, query)
+        })
+}
+
+// Narrows the candidates by stretch, then style, then weight.
+fn select_in_family<'a>(candidates: &[&'a FaceInfo], query: &Query) -> Option<&'a FaceInfo> {
+    let stretch = candidates
+        .iter()
+        .map(|face| face.stretch)
+        .min_by_key(|stretch| stretch_rank(query.stretch, *stretch))?;
+    let candidates = candidates
+        .iter()
+        .filter(|face| face.stretch == stretch)
+        .copied()
+        .collect::<Vec<_>>();
+    let style = style_fallbacks(query.style)
+        .iter()
+        .find(|style| candidates.iter().any(|face| face.style == **style))?;
+    candidates
+        .iter()
+        .filter(|face| face.style == *style)
+        .min_by_key(|face| weight_rank(query.weight as i32, face.weight as i32))
+        .copied()
+}
+
+// Condensed requests prefer narrower faces first, expanded requests wider ones.
+fn stretch_rank(desired: Stretch, stretch: Stretch) -> (u8, u8) {
+    let (desired, stretch) = (desired as u8, stretch as u8);
+    let narrow_first = desired <= Stretch::Normal as u8;
+    match stretch.cmp(&desired) {
+        std::cmp::Ordering::Equal => (0, 0),
+        std::cmp::Ordering::Less if narrow_first => (1, desired - stretch),
+        std::cmp::Ordering::Greater if !narrow_first => (1, stretch - desired),
+        _ => (2, desired.abs_diff(stretch)),
+    }
+}
+
+fn style_fallbacks(style: Style) -> &'static [Style] {
+    match style {
+        Style::Normal => &[Style::Normal, Style::Oblique, Style::Italic],
+        Style::Italic => &[Style::Italic, Style::Oblique, Style::Normal],
+        Style::Oblique => &[Style::Oblique, Style::Italic, Style::Normal],
+    }
+}
+
+// Lower is better. Each tier of the CSS weight rules gets its own band so that e.g. for
+// a desired weight of 400 every weight up to 500 beats every lighter weight, which in
+// turn beats every weight above 500.
+fn weight_rank(desired: i32, weight: i32) -> (u8, i32) {
+    let distance = (weight - desired).abs();
+    if weight == desired {
+        (0, 0)
+    } else if (400..=500).contains(&desired) {
+        if weight > desired && weight <= 500 {
+            (1, distance)
+        } else if weight < desired {
+            (2, distance)
+        } else {
+            (3, distance)
+        }
+    } else if desired < 400 {
+        if weight < desired {
+            (1, distance)
+        } else {
+            (2, distance)
+        }
+    } else if weight > desired {
+        (1, distance)
+    } else {
+        (2, distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Source;
+
+    fn face(family: &str, weight: Weight, style: Style, stretch: Stretch, monospace: bool) -> FaceInfo {
+        FaceInfo {
+            family: family.to_string(),
+            weight,
+            style,
+            stretch,
+            monospace,
+            src: Source::Builtin,
+            index: 0,
+            data: None,
+        }
+    }
+
+    fn query(weight: Weight, style: Style, stretch: Stretch) -> Query {
+        Query {
+            families: vec![Family::Named("Test".into())],
+            weight,
+            style,
+            stretch,
+        }
+    }
+
+    fn weights(weights: &[Weight]) -> Vec<FaceInfo> {
+        weights
+            .iter()
+            .map(|weight| face("Test", *weight, Style::Normal, Stretch::Normal, false))
+            .collect()
+    }
+
+    fn select_weight(faces: &[FaceInfo], weight: Weight) -> Option<Weight> {
+        select(faces, &query(weight, Style::Normal, Stretch::Normal)).map(|face| face.weight)
+    }
+
+    #[test]
+    fn normal_weight_prefers_medium_then_lighter() {
+        let faces = weights(&[Weight::Light, Weight::Medium, Weight::Bold]);
+        assert_eq!(select_weight(&faces, Weight::Normal), Some(Weight::Medium));
+        let faces = weights(&[Weight::Light, Weight::Bold]);
+        assert_eq!(select_weight(&faces, Weight::Normal), Some(Weight::Light));
+        let faces = weights(&[Weight::Semibold, Weight::Bold]);
+        assert_eq!(select_weight(&faces, Weight::Normal), Some(Weight::Semibold));
+    }
+
+    #[test]
+    fn bold_weights_prefer_heavier_then_lighter() {
+        let faces = weights(&[Weight::Medium, Weight::Bold]);
+        assert_eq!(select_weight(&faces, Weight::Semibold), Some(Weight::Bold));
+        let faces = weights(&[Weight::Light, Weight::Medium]);
+        assert_eq!(select_weight(&faces, Weight::Semibold), Some(Weight::Medium));
+    }
+
+    #[test]
+    fn light_weights_prefer_lighter_then_heavier() {
+        let faces = weights(&[Weight::Thin, Weight::Normal]);
+        assert_eq!(select_weight(&faces, Weight::Light), Some(Weight::Thin));
+    }
+
+    #[test]
+    fn weight_rank_orders_the_tiers() {
+        assert!(weight_rank(400, 500) < weight_rank(400, 300));
+        assert!(weight_rank(400, 300) < weight_rank(400, 600));
+        assert!(weight_rank(600, 700) < weight_rank(600, 500));
+        assert!(weight_rank(300, 200) < weight_rank(300, 400));
+    }
+
+    #[test]
+    fn italic_falls_back_to_oblique_then_normal() {
+        assert_eq!(style_fallbacks(Style::Italic), [Style::Italic, Style::Oblique, Style::Normal]);
+        let faces = [
+            face("Test", Weight::Normal, Style::Normal, Stretch::Normal, false),
+            face("Test", Weight::Normal, Style::Oblique, Stretch::Normal, false),
+        ];
+        let found = select(&faces, &query(Weight::Normal, Style::Italic, Stretch::Normal));
+        assert_eq!(found.map(|face| face.style), Some(Style::Oblique));
+        let found = select(&faces[..1], &query(Weight::Normal, Style::Italic, Stretch::Normal));
+        assert_eq!(found.map(|face| face.style), Some(Style::Normal));
+    }
+
+    #[test]
+    fn condensed_prefers_narrower_faces_first() {
+        assert!(stretch_rank(Stretch::Condensed, Stretch::ExtraCondensed) < stretch_rank(Stretch::Condensed, Stretch::SemiCondensed));
+        assert!(stretch_rank(Stretch::Expanded, Stretch::ExtraExpanded) < stretch_rank(Stretch::Expanded, Stretch::SemiExpanded));
+        let faces = [
+            face("Test", Weight::Normal, Style::Normal, Stretch::UltraCondensed, false),
+            face("Test", Weight::Normal, Style::Normal, Stretch::Normal, false),
+        ];
+        let found = select(&faces, &query(Weight::Normal, Style::Normal, Stretch::Condensed));
+        assert_eq!(found.map(|face| face.stretch), Some(Stretch::UltraCondensed));
+    }
+
+    #[test]
+    fn monospace_falls_back_to_any_monospaced_face() {
+        let faces = [
+            face("Sans", Weight::Normal, Style::Normal, Stretch::Normal, false),
+            face("Some Mono", Weight::Normal, Style::Normal, Stretch::Normal, true),
+        ];
+        let monospace = Query {
+            families: vec![Family::Monospace],
+            ..Query::default()
+        };
+        assert_eq!(select(&faces, &monospace).map(|face| face.family.as_str()), Some("Some Mono"));
+        assert_eq!(select(&faces, &Query::default()), None);
+    }
+}
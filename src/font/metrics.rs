@@ -0,0 +1,90 @@
+use super::{Error, Font};
+
+/// Vertical metrics of a face scaled to a pixel size, y grows upwards from the baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// The pixel size the metrics were scaled to, i.e. the size of one em.
+    pub size: f32,
+    pub units_per_em: u16,
+    pub ascender: f32,
+    /// Negative for faces that descend below the baseline.
+    pub descender: f32,
+    pub line_gap: f32,
+    pub cap_height: Option<f32>,
+    pub x_height: Option<f32>,
+    pub underline: Option<DecorationMetrics>,
+    pub strikeout: Option<DecorationMetrics>,
+}
+
+impl FontMetrics {
+    /// The distance between two consecutive baselines.
+    pub fn line_height(&self) -> f32 {
+        self.ascender - self.descender + self.line_gap
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.size / self.units_per_em as f32
+    }
+}
+
+/// Position and thickness of an underline or strikeout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecorationMetrics {
+    pub position: f32,
+    pub thickness: f32,
+}
+
+impl Font {
+    pub fn metrics(&self, size: f32) -> Result<FontMetrics, Error> {
+        let face = self.face()?;
+        let units_per_em = face.units_per_em();
+        let scale = size / units_per_em as f32;
+        let line = |metrics: ttf_parser::LineMetrics| DecorationMetrics {
+            position: metrics.position as f32 * scale,
+            thickness: metrics.thickness as f32 * scale,
+        };
+        Ok(FontMetrics {
+            size,
+            units_per_em,
+            ascender: face.ascender() as f32 * scale,
+            descender: face.descender() as f32 * scale,
+            line_gap: face.line_gap() as f32 * scale,
+            cap_height: face.capital_height().map(|height| height as f32 * scale),
+            x_height: face.x_height().map(|height| height as f32 * scale),
+            underline: face.underline_metrics().map(line),
+            strikeout: face.strikeout_metrics().map(line),
+        })
+    }
+
+    /// The horizontal advance of a glyph at `size` pixels, including synthetic bold.
+    pub fn glyph_advance(&self, glyph_id: u16, size: f32) -> Option<f32> {
+        let face = self.face().ok()?;
+        let advance = face.glyph_hor_advance(ttf_parser::GlyphId(glyph_id))? as f32 + self.synthesis.advance(face.units_per_em());
+        Some(advance * size / face.units_per_em() as f32)
+    }
+
+    /// The horizontal advance of the glyph `c` maps to, without any shaping applied.
+    pub fn char_advance(&self, c: char, size: f32) -> Option<f32> {
+        let glyph_id = self.face().ok()?.glyph_index(c)?;
+        self.glyph_advance(glyph_id.0, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roboto_metrics_scale_to_the_pixel_size() {
+        let font = Font::from_data(include_bytes!("../fonts/Roboto/Roboto-Regular.ttf") as &[u8], Default::default()).unwrap();
+        let metrics = font.metrics(16.0).unwrap();
+        // Roboto's values in font units, at 16 pixels per em one unit is 1/128 of a pixel.
+        let px = |units: f32| units * 16.0 / 2048.0;
+        assert_eq!((metrics.size, metrics.units_per_em, metrics.scale()), (16.0, 2048, px(1.0)));
+        assert_eq!((metrics.ascender, metrics.descender, metrics.line_gap), (px(1900.0), px(-500.0), 0.0));
+        assert_eq!(metrics.line_height(), px(2400.0));
+        assert_eq!((metrics.cap_height, metrics.x_height), (Some(px(1456.0)), Some(px(1082.0))));
+        assert_eq!(metrics.underline, Some(DecorationMetrics { position: px(-150.0), thickness: px(100.0) }));
+        assert_eq!(metrics.strikeout, Some(DecorationMetrics { position: px(512.0), thickness: px(102.0) }));
+    }
+}
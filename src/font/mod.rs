@@ -0,0 +1,500 @@
+use std::{
+    borrow::Cow,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+mod builtin;
+pub(crate) mod color;
+mod database;
+mod features;
+mod fetch;
+mod id;
+mod matching;
+mod metrics;
+mod size;
+mod synthesis;
+mod variation;
+mod woff;
+
+pub use builtin::{bundled, bundled_variable, BundledFace};
+pub use database::{FaceInfo, FontDatabase};
+pub use features::{Feature, FontFeatures};
+pub use fetch::{FetchFuture, FileFetcher, FontFetcher, FontLoader};
+pub use id::FontId;
+pub use matching::Query;
+pub use metrics::{DecorationMetrics, FontMetrics};
+pub use size::FontSize;
+pub use synthesis::Synthesis;
+pub use variation::{NamedInstance, Variation, VariationAxis};
+
+/// Fonts compare and hash by [`FontId`]. The fields it is made of are read through getters,
+/// so the id cached on the font can't go stale.
+#[derive(Debug, Clone)]
+pub struct Font {
+    pub family: Family,
+    pub weight: Weight,
+    pub style: Style,
+    pub stretch: Stretch,
+    pub monospace: bool,
+    pub(crate) variations: Vec<Variation>,
+    pub src: Source,
+    pub(crate) data: FontData,
+    pub(crate) index: u32,
+    pub(crate) synthesis: Synthesis,
+    id: FontId,
+}
+
+impl Font {
+    /// DejaVu Serif, the only face bundled regardless of features.
+    pub const DEFAULT: Font = Font {
+        family: Family::Named(Cow::Borrowed("DejaVu Serif")),
+        weight: Weight::Normal,
+        style: Style::Normal,
+        stretch: Stretch::Normal,
+        monospace: false,
+        variations: Vec::new(),
+        src: Source::Builtin,
+        data: FontData::Static(include_bytes!("../fonts/DejaVuSerif.ttf")),
+        index: 0,
+        synthesis: Synthesis::NONE,
+        id: FontId::DEFAULT,
+    };
+
+    #[cfg(feature = "source-code-pro")]
+    pub const MONOSPACE: Font = Font {
+        family: Family::Named(Cow::Borrowed("Source Code Pro")),
+        weight: Weight::Medium,
+        style: Style::Normal,
+        stretch: Stretch::Normal,
+        monospace: true,
+        variations: Vec::new(),
+        src: Source::Builtin,
+        data: FontData::Static(include_bytes!("../fonts/Source_Code_Pro/static/SourceCodePro-Medium.ttf")),
+        index: 0,
+        synthesis: Synthesis::NONE,
+        id: FontId::MONOSPACE,
+    };
+
+    #[cfg(feature = "cairo")]
+    pub const CAIRO: Font = Font {
+        family: Family::Named(Cow::Borrowed("Cairo")),
+        weight: Weight::Normal,
+        style: Style::Normal,
+        stretch: Stretch::Normal,
+        monospace: false,
+        variations: Vec::new(),
+        src: Source::Builtin,
+        data: FontData::Static(include_bytes!("../fonts/Cairo/static/Cairo-Regular.ttf")),
+        index: 0,
+        synthesis: Synthesis::NONE,
+        id: FontId::CAIRO,
+    };
+
+    /// Loads a TTF/OTF file from disk.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_path_with_index(path, 0)
+    }
+
+    /// Loads a single face of a font collection from disk.
+    pub fn from_path_with_index(path: impl AsRef<Path>, index: u32) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        Self::from_data_with_index(data, index, Source::Path(path.to_path_buf()))
+    }
+
+    /// Loads every face of a font file, a plain TTF/OTF yields a single face.
+    pub fn collection_from_path(path: impl AsRef<Path>) -> Result<Vec<Self>, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        Self::collection_from_data(data, Source::Path(path.to_path_buf()))
+    }
+
+    /// Creates a font from raw font file bytes, reading family, weight, style,
+    /// stretch and monospace flag from the face itself.
+    pub fn from_data(data: impl Into<FontData>, src: Source) -> Result<Self, Error> {
+        Self::from_data_with_index(data, 0, src)
+    }
+
+    pub fn collection_from_data(data: impl Into<FontData>, src: Source) -> Result<Vec<Self>, Error> {
+        let data = woff::decode(data.into())?;
+        (0..Self::face_count(&data))
+            .map(|index| Self::from_data_with_index(data.clone(), index, src.clone()))
+            .collect()
+    }
+
+    /// WOFF and WOFF2 data is decoded to plain sfnt data first.
+    pub fn from_data_with_index(data: impl Into<FontData>, index: u32, src: Source) -> Result<Self, Error> {
+        let data = woff::decode(data.into())?;
+        let face = ttf_parser::Face::parse(&data, index)?;
+        let family = Self::family_name(&face)
+            .map(|name| Family::Named(Cow::Owned(name)))
+            .unwrap_or_default();
+        let weight = Weight::from(face.weight().to_number());
+        let style = face.style().into();
+        let stretch = face.width().into();
+        let monospace = face.is_monospaced();
+        Ok(Self {
+            family,
+            weight,
+            style,
+            stretch,
+            monospace,
+            variations: Vec::new(),
+            src,
+            id: FontId::intern(&data, index, &[], Synthesis::NONE),
+            data,
+            index,
+            synthesis: Synthesis::NONE,
+        })
+    }
+
+    /// The number of faces in a font file, 1 unless it is a collection.
+    pub fn face_count(data: &[u8]) -> u32 {
+        ttf_parser::fonts_in_collection(data).unwrap_or(1)
+    }
+
+    /// Sets the coordinate of one axis, replacing any previous value for it.
+    /// Weight, width and slant also update the descriptor so matching sees the instance.
+    pub fn with_variation(mut self, variation: Variation) -> Self {
+        match variation.axis {
+            Variation::WEIGHT => self.weight = Weight::from(variation.value.clamp(1.0, 1000.0).round() as u16),
+            Variation::WIDTH => self.stretch = Stretch::from_percentage(variation.value),
+            // Italic faces stay italic, an upright face is oblique for as long as it's slanted.
+            Variation::SLANT if self.style != Style::Italic => {
+                self.style = if variation.value == 0.0 { Style::Normal } else { Style::Oblique };
+            }
+            _ => {}
+        }
+        self.variations.retain(|v| v.axis != variation.axis);
+        self.variations.push(variation);
+        self.id = FontId::intern(&self.data, self.index, &self.variations, self.synthesis);
+        self
+    }
+
+    pub fn with_synthesis(mut self, synthesis: Synthesis) -> Self {
+        self.synthesis = synthesis;
+        self.id = FontId::intern(&self.data, self.index, &self.variations, self.synthesis);
+        self
+    }
+
+    pub fn with_named_instance(self, instance: &NamedInstance) -> Self {
+        instance
+            .variations
+            .iter()
+            .fold(self, |font, variation| font.with_variation(*variation))
+    }
+
+    pub fn data(&self) -> &FontData {
+        &self.data
+    }
+
+    /// The face within [`Font::data`], only ever non-zero for collections (.ttc/.otc).
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Coordinates applied to the axes of a variable font, both when shaping and when extracting outlines.
+    pub fn variations(&self) -> &[Variation] {
+        &self.variations
+    }
+
+    /// Styles faked by the renderer, set by [`FontDatabase::query`].
+    pub fn synthesis(&self) -> Synthesis {
+        self.synthesis
+    }
+
+    pub fn variation_axes(&self) -> Vec<VariationAxis> {
+        self.face().map(|face| variation::axes(&face)).unwrap_or_default()
+    }
+
+    pub fn named_instances(&self) -> Vec<NamedInstance> {
+        self.face()
+            .map(|face| variation::named_instances(&face))
+            .unwrap_or_default()
+    }
+
+    /// Parses the face with the font's variation coordinates applied.
+    pub(crate) fn face(&self) -> Result<ttf_parser::Face<'_>, ttf_parser::FaceParsingError> {
+        let mut face = ttf_parser::Face::parse(&self.data, self.index)?;
+        for variation in &self.variations {
+            face.set_variation(ttf_parser::Tag::from_bytes(&variation.axis), variation.value);
+        }
+        Ok(face)
+    }
+
+    pub(crate) fn family_name(face: &ttf_parser::Face) -> Option<String> {
+        Self::find_name(face, ttf_parser::name_id::TYPOGRAPHIC_FAMILY)
+            .or_else(|| Self::find_name(face, ttf_parser::name_id::FAMILY))
+    }
+
+    pub(crate) fn find_name(face: &ttf_parser::Face, id: u16) -> Option<String> {
+        face.names()
+            .into_iter()
+            .filter(|name| name.name_id == id && name.is_unicode())
+            .find_map(|name| name.to_string())
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Self {
+            family: Family::default(),
+            weight: Weight::default(),
+            style: Style::default(),
+            stretch: Stretch::default(),
+            monospace: false,
+            variations: Vec::new(),
+            src: Source::default(),
+            data: FontData::default(),
+            index: 0,
+            synthesis: Synthesis::NONE,
+            id: FontId::intern(&FontData::default(), 0, &[], Synthesis::NONE),
+        }
+    }
+}
+
+/// The raw bytes of a font file, either compiled into the binary or loaded at runtime.
+#[derive(Debug, Clone)]
+pub enum FontData {
+    Static(&'static [u8]),
+    Shared(Arc<[u8]>),
+}
+
+impl Default for FontData {
+    fn default() -> Self {
+        Self::Static(&[])
+    }
+}
+
+impl Deref for FontData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Static(data) => data,
+            Self::Shared(data) => data,
+        }
+    }
+}
+
+impl From<&'static [u8]> for FontData {
+    fn from(data: &'static [u8]) -> Self {
+        Self::Static(data)
+    }
+}
+
+impl From<Arc<[u8]>> for FontData {
+    fn from(data: Arc<[u8]>) -> Self {
+        Self::Shared(data)
+    }
+}
+
+impl From<Vec<u8>> for FontData {
+    fn from(data: Vec<u8>) -> Self {
+        Self::Shared(data.into())
+    }
+}
+
+impl AsRef<[u8]> for FontData {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Hash for FontData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.deref().hash(state)
+    }
+}
+
+impl PartialEq for FontData {
+    fn eq(&self, other: &Self) -> bool {
+        self.deref() == other.deref()
+    }
+}
+
+impl Eq for FontData {}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Parse(ttf_parser::FaceParsingError),
+    UnsupportedUrl(String),
+    Fetch(Box<dyn std::error::Error + Send + Sync>),
+    Decode(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read font: {err}"),
+            Self::Parse(err) => write!(f, "failed to parse font: {err}"),
+            Self::UnsupportedUrl(url) => write!(f, "no font fetcher can resolve {url}"),
+            Self::Fetch(err) => write!(f, "failed to fetch font: {err}"),
+            Self::Decode(msg) => write!(f, "failed to decode font: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::UnsupportedUrl(_) | Self::Decode(_) => None,
+            Self::Fetch(err) => Some(err.as_ref()),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ttf_parser::FaceParsingError> for Error {
+    fn from(err: ttf_parser::FaceParsingError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub enum Source {
+    #[default]
+    Builtin,
+    Path(PathBuf),
+    Url(&'static str),
+}
+
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub enum Family {
+    Named(Cow<'static, str>),
+    Serif,
+    #[default]
+    SansSerif,
+    Cursive,
+    Monospace,
+}
+
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub enum Weight {
+    Thin = 100,
+    ExtraLigh = 200,
+    Light = 300,
+    #[default]
+    Normal = 400,
+    Medium = 500,
+    Semibold = 600,
+    Bold = 700,
+    ExtraBold = 800,
+    Black = 900,
+}
+
+impl From<u16> for Weight {
+    /// Rounds an OS/2 `usWeightClass` to the nearest named weight.
+    fn from(value: u16) -> Self {
+        match value.saturating_add(50) / 100 {
+            0 | 1 => Self::Thin,
+            2 => Self::ExtraLigh,
+            3 => Self::Light,
+            4 => Self::Normal,
+            5 => Self::Medium,
+            6 => Self::Semibold,
+            7 => Self::Bold,
+            8 => Self::ExtraBold,
+            _ => Self::Black,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub enum Style {
+    #[default]
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl From<ttf_parser::Style> for Style {
+    fn from(style: ttf_parser::Style) -> Self {
+        match style {
+            ttf_parser::Style::Normal => Self::Normal,
+            ttf_parser::Style::Italic => Self::Italic,
+            ttf_parser::Style::Oblique => Self::Oblique,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Stretch {
+    UltraCondensed = 1,
+    ExtraCondensed = 2,
+    Condensed = 3,
+    SemiCondensed = 4,
+    #[default]
+    Normal = 5,
+    SemiExpanded = 6,
+    Expanded = 7,
+    ExtraExpanded = 8,
+    UltraExpanded = 9,
+}
+
+impl Stretch {
+    /// The width relative to [`Stretch::Normal`], e.g. `87.5` for [`Stretch::SemiCondensed`].
+    pub fn to_percentage(self) -> f32 {
+        match self {
+            Self::UltraCondensed => 50.0,
+            Self::ExtraCondensed => 62.5,
+            Self::Condensed => 75.0,
+            Self::SemiCondensed => 87.5,
+            Self::Normal => 100.0,
+            Self::SemiExpanded => 112.5,
+            Self::Expanded => 125.0,
+            Self::ExtraExpanded => 150.0,
+            Self::UltraExpanded => 200.0,
+        }
+    }
+
+    /// The named stretch closest to a `wdth` axis value.
+    pub fn from_percentage(percentage: f32) -> Self {
+        [
+            Self::UltraCondensed,
+            Self::ExtraCondensed,
+            Self::Condensed,
+            Self::SemiCondensed,
+            Self::Normal,
+            Self::SemiExpanded,
+            Self::Expanded,
+            Self::ExtraExpanded,
+            Self::UltraExpanded,
+        ]
+        .into_iter()
+        .min_by(|a, b| {
+            (a.to_percentage() - percentage)
+                .abs()
+                .total_cmp(&(b.to_percentage() - percentage).abs())
+        })
+        .unwrap_or_default()
+    }
+}
+
+impl From<ttf_parser::Width> for Stretch {
+    fn from(width: ttf_parser::Width) -> Self {
+        match width {
+            ttf_parser::Width::UltraCondensed => Self::UltraCondensed,
+            ttf_parser::Width::ExtraCondensed => Self::ExtraCondensed,
+            ttf_parser::Width::Condensed => Self::Condensed,
+            ttf_parser::Width::SemiCondensed => Self::SemiCondensed,
+            ttf_parser::Width::Normal => Self::Normal,
+            ttf_parser::Width::SemiExpanded => Self::SemiExpanded,
+            ttf_parser::Width::Expanded => Self::Expanded,
+            ttf_parser::Width::ExtraExpanded => Self::ExtraExpanded,
+            ttf_parser::Width::UltraExpanded => Self::UltraExpanded,
+        }
+    }
+}
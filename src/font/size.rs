@@ -0,0 +1,24 @@
+/// The size of one em, converted to physical pixels with the window's scale factor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontSize {
+    /// Logical pixels, 1/96 of an inch.
+    Pixels(f32),
+    /// Points, 1/72 of an inch.
+    Points(f32),
+}
+
+impl FontSize {
+    pub fn to_physical(self, scale_factor: f64) -> f32 {
+        let logical = match self {
+            FontSize::Pixels(pixels) => pixels,
+            FontSize::Points(points) => points * 96.0 / 72.0,
+        };
+        logical * scale_factor as f32
+    }
+}
+
+impl Default for FontSize {
+    fn default() -> Self {
+        FontSize::Pixels(16.0)
+    }
+}
@@ -0,0 +1,37 @@
+use super::{FaceInfo, Query, Style, Weight};
+
+/// Styles the renderer fakes because the resolved face doesn't have them.
+/// Bold lowers the distance field threshold and widens advances, oblique shears the glyph quads.
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub struct Synthesis {
+    pub bold: bool,
+    pub oblique: bool,
+}
+
+impl Synthesis {
+    pub const NONE: Synthesis = Synthesis {
+        bold: false,
+        oblique: false,
+    };
+
+    /// How far a synthetic bold outline grows on each side, in ems.
+    pub const EMBOLDEN: f32 = 1.0 / 96.0;
+    /// Horizontal shift per unit of height of a synthetic oblique, roughly 12°.
+    pub const SKEW: f32 = 0.21;
+
+    /// What has to be synthesized to render `face` the way `query` asked for it.
+    pub fn resolve(query: &Query, face: &FaceInfo) -> Self {
+        Self {
+            bold: query.weight as u16 >= Weight::Semibold as u16 && (face.weight as u16) < Weight::Semibold as u16,
+            oblique: query.style != Style::Normal && face.style == Style::Normal,
+        }
+    }
+
+    /// The extra advance every glyph gets, in font units.
+    pub fn advance(&self, units_per_em: u16) -> f32 {
+        match self.bold {
+            true => 2.0 * Self::EMBOLDEN * units_per_em as f32,
+            false => 0.0,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::hash::{Hash, Hasher};
+
+use ttf_parser::Tag;
+
+/// A coordinate on one of the axes of a variable font, e.g. `wght=650` or `slnt=-8`.
+#[derive(Debug, Clone, Copy)]
+pub struct Variation {
+    pub axis: [u8; 4],
+    pub value: f32,
+}
+
+impl Variation {
+    pub const WEIGHT: [u8; 4] = *b"wght";
+    pub const SLANT: [u8; 4] = *b"slnt";
+    pub const WIDTH: [u8; 4] = *b"wdth";
+
+    pub fn new(axis: [u8; 4], value: f32) -> Self {
+        Self { axis, value }
+    }
+}
+
+impl PartialEq for Variation {
+    fn eq(&self, other: &Self) -> bool {
+        self.axis == other.axis && self.value.to_bits() == other.value.to_bits()
+    }
+}
+
+impl Eq for Variation {}
+
+impl Hash for Variation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.axis.hash(state);
+        self.value.to_bits().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariationAxis {
+    pub axis: [u8; 4],
+    pub name: Option<String>,
+    pub min: f32,
+    pub default: f32,
+    pub max: f32,
+}
+
+/// A predefined instance from the `fvar` table, e.g. "SemiBold Italic".
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedInstance {
+    pub name: String,
+    pub variations: Vec<Variation>,
+}
+
+pub(crate) fn axes(face: &ttf_parser::Face) -> Vec<VariationAxis> {
+    face.variation_axes()
+        .into_iter()
+        .map(|axis| VariationAxis {
+            axis: axis.tag.to_bytes(),
+            name: super::Font::find_name(face, axis.name_id),
+            min: axis.min_value,
+            default: axis.def_value,
+            max: axis.max_value,
+        })
+        .collect()
+}
+
+// ttf-parser only exposes the axes of `fvar`, the instance records that follow them are read by hand.
+pub(crate) fn named_instances(face: &ttf_parser::Face) -> Vec<NamedInstance> {
+    let Some(data) = face.raw_face().table(Tag::from_bytes(b"fvar")) else {
+        return Vec::new();
+    };
+    let read_u16 = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+    };
+    let read_fixed = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 65536.0)
+    };
+    let (Some(axes_offset), Some(axis_count), Some(axis_size), Some(instance_count), Some(instance_size)) =
+        (read_u16(4), read_u16(8), read_u16(10), read_u16(12), read_u16(14))
+    else {
+        return Vec::new();
+    };
+    let tags = face
+        .variation_axes()
+        .into_iter()
+        .map(|axis| axis.tag.to_bytes())
+        .collect::<Vec<_>>();
+    let instances_offset = axes_offset + axis_count * axis_size;
+
+    (0..instance_count)
+        .filter_map(|index| {
+            let offset = instances_offset + index * instance_size;
+            let name_id = read_u16(offset)?;
+            let variations = tags
+                .iter()
+                .enumerate()
+                .map(|(i, axis)| Some(Variation::new(*axis, read_fixed(offset + 4 + i * 4)?)))
+                .collect::<Option<Vec<_>>>()?;
+            Some(NamedInstance {
+                name: super::Font::find_name(face, name_id as u16).unwrap_or_default(),
+                variations,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,663 @@
+use std::io::Read;
+
+use super::{Error, FontData};
+
+const WOFF: [u8; 4] = *b"wOFF";
+const WOFF2: [u8; 4] = *b"wOF2";
+const TTCF: u32 = u32::from_be_bytes(*b"ttcf");
+
+const GLYF: u32 = u32::from_be_bytes(*b"glyf");
+const LOCA: u32 = u32::from_be_bytes(*b"loca");
+const HMTX: u32 = u32::from_be_bytes(*b"hmtx");
+const HHEA: u32 = u32::from_be_bytes(*b"hhea");
+const MAXP: u32 = u32::from_be_bytes(*b"maxp");
+const HEAD: u32 = u32::from_be_bytes(*b"head");
+
+// Tags a WOFF2 table directory can refer to by index instead of spelling them out.
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// Unwraps WOFF and WOFF2 containers into plain sfnt data, anything else is passed through untouched.
+pub(crate) fn decode(data: FontData) -> Result<FontData, Error> {
+    match data.get(0..4) {
+        Some(magic) if magic == WOFF => Ok(decode_woff(&data)?.into()),
+        Some(magic) if magic == WOFF2 => Ok(decode_woff2(&data)?.into()),
+        _ => Ok(data),
+    }
+}
+
+fn decode_woff(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut header = Reader::new(data);
+    header.skip(4)?;
+    let flavor = header.u32()?;
+    header.skip(4)?;
+    let num_tables = header.u16()?;
+    if num_tables == 0 {
+        return Err(Error::Decode("WOFF font without tables"));
+    }
+
+    let mut directory = Reader::new(data);
+    directory.skip(44)?;
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let tag = directory.u32()?;
+        let offset = directory.u32()? as usize;
+        let compressed_length = directory.u32()? as usize;
+        let length = directory.u32()? as usize;
+        directory.skip(4)?;
+
+        let compressed = data
+            .get(offset..offset + compressed_length)
+            .ok_or(Error::Decode("WOFF table out of bounds"))?;
+        // The lengths are untrusted, the table grows with the data actually inflated and one byte
+        // past the declared length is enough to tell it's wrong.
+        let table = if compressed_length < length {
+            let mut table = Vec::new();
+            flate2::read::ZlibDecoder::new(compressed)
+                .take(length as u64 + 1)
+                .read_to_end(&mut table)
+                .map_err(|_| Error::Decode("corrupt zlib stream in WOFF table"))?;
+            table
+        } else {
+            compressed.to_vec()
+        };
+        if table.len() != length {
+            return Err(Error::Decode("WOFF table has the wrong length"));
+        }
+        tables.push((tag, table));
+    }
+
+    Ok(build_sfnt(flavor, tables))
+}
+
+fn decode_woff2(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut header = Reader::new(data);
+    header.skip(4)?;
+    let flavor = header.u32()?;
+    header.skip(4)?;
+    let num_tables = header.u16()?;
+    header.skip(6)?;
+    let compressed_length = header.u32()? as usize;
+    header.skip(24)?;
+
+    if flavor == TTCF {
+        return Err(Error::Decode("WOFF2 font collections are not supported"));
+    }
+    if num_tables == 0 {
+        return Err(Error::Decode("WOFF2 font without tables"));
+    }
+
+    // (tag, transformed, length in the decompressed stream)
+    let mut directory = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let flags = header.u8()?;
+        let tag = match flags & 0x3f {
+            63 => header.u32()?,
+            index => u32::from_be_bytes(*KNOWN_TAGS[index as usize]),
+        };
+        let version = flags >> 6;
+        let length = header.base128()?;
+        // For glyf and loca version 0 is the transform, version 3 the null transform.
+        let transformed = match tag {
+            GLYF | LOCA => version == 0,
+            _ => version != 0,
+        };
+        let length = if transformed { header.base128()? } else { length };
+        if transformed && !matches!(tag, GLYF | LOCA | HMTX) {
+            return Err(Error::Decode("unknown WOFF2 table transform"));
+        }
+        directory.push((tag, transformed, length as usize));
+    }
+
+    let compressed = data
+        .get(header.offset..header.offset + compressed_length)
+        .ok_or(Error::Decode("WOFF2 data out of bounds"))?;
+    // Nothing past the tables of the directory is read, a stream can't inflate without bound.
+    let stream_length = directory.iter().map(|(_, _, length)| *length as u64).sum::<u64>();
+    let mut stream = Vec::new();
+    brotli_decompressor::Decompressor::new(compressed, 4096)
+        .take(stream_length)
+        .read_to_end(&mut stream)
+        .map_err(|_| Error::Decode("corrupt brotli stream in WOFF2 data"))?;
+
+    let mut offset = 0;
+    let mut tables = Vec::with_capacity(directory.len());
+    for (tag, _, length) in &directory {
+        let table = stream
+            .get(offset..offset + length)
+            .ok_or(Error::Decode("WOFF2 table out of bounds"))?;
+        tables.push((*tag, table.to_vec()));
+        offset += length;
+    }
+
+    let is_transformed = |tag: u32| directory.iter().any(|(t, transformed, _)| *t == tag && *transformed);
+    let table = |tables: &[(u32, Vec<u8>)], tag: u32| {
+        tables
+            .iter()
+            .position(|(t, _)| *t == tag)
+            .ok_or(Error::Decode("WOFF2 font is missing a required table"))
+    };
+
+    if is_transformed(GLYF) {
+        let glyf = table(&tables, GLYF)?;
+        let loca = table(&tables, LOCA)?;
+        let reconstructed = reconstruct_glyf(&tables[glyf].1)?;
+        tables[glyf].1 = reconstructed.glyf;
+        tables[loca].1 = reconstructed.loca;
+
+        if is_transformed(HMTX) {
+            let num_h_metrics = Reader::at(&tables[table(&tables, HHEA)?].1, 34)?.u16()?;
+            let num_glyphs = Reader::at(&tables[table(&tables, MAXP)?].1, 4)?.u16()?;
+            let hmtx = table(&tables, HMTX)?;
+            tables[hmtx].1 = reconstruct_hmtx(&tables[hmtx].1, num_glyphs, num_h_metrics, &reconstructed.x_mins)?;
+        }
+    } else if is_transformed(HMTX) {
+        return Err(Error::Decode("WOFF2 hmtx transform requires a transformed glyf table"));
+    }
+
+    Ok(build_sfnt(flavor, tables))
+}
+
+struct Glyf {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    x_mins: Vec<i16>,
+}
+
+// WOFF2 §5.1, glyphs are split into separate streams that are stitched back together here.
+fn reconstruct_glyf(data: &[u8]) -> Result<Glyf, Error> {
+    const ON_CURVE: u8 = 0x01;
+    const X_SHORT: u8 = 0x02;
+    const Y_SHORT: u8 = 0x04;
+    const X_SAME_OR_POSITIVE: u8 = 0x10;
+    const Y_SAME_OR_POSITIVE: u8 = 0x20;
+    const OVERLAP_SIMPLE: u8 = 0x40;
+
+    let mut header = Reader::new(data);
+    header.skip(2)?;
+    let option_flags = header.u16()?;
+    let num_glyphs = header.u16()? as usize;
+    let index_format = header.u16()?;
+    let mut streams = Vec::with_capacity(7);
+    let mut offset = 36;
+    for _ in 0..7 {
+        let length = header.u32()? as usize;
+        let stream = data
+            .get(offset..offset + length)
+            .ok_or(Error::Decode("WOFF2 glyf stream out of bounds"))?;
+        streams.push(Reader::new(stream));
+        offset += length;
+    }
+    let overlap_bitmap = match option_flags & 1 {
+        0 => None,
+        _ => Some(
+            data.get(offset..offset + num_glyphs.div_ceil(8))
+                .ok_or(Error::Decode("WOFF2 overlap bitmap out of bounds"))?,
+        ),
+    };
+    let [mut n_contours, mut n_points, mut flags, mut glyphs, mut composites, mut bboxes, mut instructions]: [Reader; 7] =
+        streams.try_into().map_err(|_| Error::Decode("WOFF2 glyf header is malformed"))?;
+    let bbox_bitmap = bboxes.bytes(4 * num_glyphs.div_ceil(32))?;
+    let bit = |bitmap: &[u8], index: usize| bitmap[index >> 3] & (0x80 >> (index & 7)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+    for index in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let has_bbox = bit(bbox_bitmap, index);
+        let contours = n_contours.i16()?;
+        match contours {
+            0 => {
+                if has_bbox {
+                    return Err(Error::Decode("empty WOFF2 glyph with a bounding box"));
+                }
+                x_mins.push(0);
+            }
+            -1 => {
+                if !has_bbox {
+                    return Err(Error::Decode("composite WOFF2 glyph without a bounding box"));
+                }
+                let start = composites.offset;
+                let mut have_instructions = false;
+                loop {
+                    let flags = composites.u16()?;
+                    composites.skip(2)?;
+                    composites.skip(if flags & 0x0001 != 0 { 4 } else { 2 })?;
+                    if flags & 0x0008 != 0 {
+                        composites.skip(2)?;
+                    } else if flags & 0x0040 != 0 {
+                        composites.skip(4)?;
+                    } else if flags & 0x0080 != 0 {
+                        composites.skip(8)?;
+                    }
+                    have_instructions |= flags & 0x0100 != 0;
+                    if flags & 0x0020 == 0 {
+                        break;
+                    }
+                }
+                let components = &composites.data[start..composites.offset];
+                let bbox = [bboxes.i16()?, bboxes.i16()?, bboxes.i16()?, bboxes.i16()?];
+
+                glyf.extend(contours.to_be_bytes());
+                bbox.iter().for_each(|v| glyf.extend(v.to_be_bytes()));
+                glyf.extend(components);
+                if have_instructions {
+                    let length = glyphs.u255()?;
+                    glyf.extend(length.to_be_bytes());
+                    glyf.extend(instructions.bytes(length as usize)?);
+                }
+                x_mins.push(bbox[0]);
+            }
+            contours if contours > 0 => {
+                let mut end_points = Vec::with_capacity(contours as usize);
+                let mut total = 0usize;
+                for _ in 0..contours {
+                    total += n_points.u255()? as usize;
+                    end_points.push(total.checked_sub(1).ok_or(Error::Decode("WOFF2 contour without points"))? as u16);
+                }
+
+                let mut points = Vec::with_capacity(total);
+                let (mut x, mut y) = (0i32, 0i32);
+                for _ in 0..total {
+                    let flag = flags.u8()?;
+                    let (dx, dy) = triplet(flag & 0x7f, &mut glyphs)?;
+                    x += dx;
+                    y += dy;
+                    points.push((x as i16, y as i16, flag >> 7 == 0));
+                }
+                let instruction_length = glyphs.u255()?;
+                let instruction_bytes = instructions.bytes(instruction_length as usize)?;
+
+                let bbox = if has_bbox {
+                    [bboxes.i16()?, bboxes.i16()?, bboxes.i16()?, bboxes.i16()?]
+                } else {
+                    points.iter().fold(
+                        [i16::MAX, i16::MAX, i16::MIN, i16::MIN],
+                        |[x_min, y_min, x_max, y_max], (x, y, _)| {
+                            [x_min.min(*x), y_min.min(*y), x_max.max(*x), y_max.max(*y)]
+                        },
+                    )
+                };
+
+                glyf.extend(contours.to_be_bytes());
+                bbox.iter().for_each(|v| glyf.extend(v.to_be_bytes()));
+                end_points.iter().for_each(|v| glyf.extend(v.to_be_bytes()));
+                glyf.extend(instruction_length.to_be_bytes());
+                glyf.extend(instruction_bytes);
+
+                let mut point_flags = Vec::with_capacity(total);
+                let mut xs = Vec::with_capacity(total * 2);
+                let mut ys = Vec::with_capacity(total * 2);
+                let (mut last_x, mut last_y) = (0i32, 0i32);
+                for (x, y, on_curve) in &points {
+                    let (dx, dy) = (*x as i32 - last_x, *y as i32 - last_y);
+                    (last_x, last_y) = (*x as i32, *y as i32);
+                    let mut flag = if *on_curve { ON_CURVE } else { 0 };
+                    if dx == 0 {
+                        flag |= X_SAME_OR_POSITIVE;
+                    } else if dx.abs() < 256 {
+                        flag |= X_SHORT | if dx > 0 { X_SAME_OR_POSITIVE } else { 0 };
+                        xs.push(dx.unsigned_abs() as u8);
+                    } else {
+                        xs.extend((dx as i16).to_be_bytes());
+                    }
+                    if dy == 0 {
+                        flag |= Y_SAME_OR_POSITIVE;
+                    } else if dy.abs() < 256 {
+                        flag |= Y_SHORT | if dy > 0 { Y_SAME_OR_POSITIVE } else { 0 };
+                        ys.push(dy.unsigned_abs() as u8);
+                    } else {
+                        ys.extend((dy as i16).to_be_bytes());
+                    }
+                    point_flags.push(flag);
+                }
+                if let (Some(first), Some(bitmap)) = (point_flags.first_mut(), overlap_bitmap) {
+                    if bit(bitmap, index) {
+                        *first |= OVERLAP_SIMPLE;
+                    }
+                }
+                glyf.extend(point_flags);
+                glyf.extend(xs);
+                glyf.extend(ys);
+                x_mins.push(bbox[0]);
+            }
+            _ => return Err(Error::Decode("invalid WOFF2 contour count")),
+        }
+        glyf.resize((glyf.len() + 3) & !3, 0);
+    }
+    offsets.push(glyf.len());
+
+    let loca = match index_format {
+        0 => offsets.iter().flat_map(|offset| ((offset / 2) as u16).to_be_bytes()).collect(),
+        _ => offsets.iter().flat_map(|offset| (*offset as u32).to_be_bytes()).collect(),
+    };
+
+    Ok(Glyf { glyf, loca, x_mins })
+}
+
+// WOFF2 §5.2, decodes one point delta from the glyph stream.
+fn triplet(flag: u8, glyphs: &mut Reader) -> Result<(i32, i32), Error> {
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+    let flag_i = flag as i32;
+    Ok(match flag {
+        0..=9 => {
+            let b0 = glyphs.u8()? as i32;
+            (0, with_sign(flag, ((flag_i & 14) << 7) + b0))
+        }
+        10..=19 => {
+            let b0 = glyphs.u8()? as i32;
+            (with_sign(flag, (((flag_i - 10) & 14) << 7) + b0), 0)
+        }
+        20..=83 => {
+            let (b0, b1) = (flag_i - 20, glyphs.u8()? as i32);
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0c) << 2) + (b1 & 0x0f)),
+            )
+        }
+        84..=119 => {
+            let (b0, b1, b2) = (flag_i - 84, glyphs.u8()? as i32, glyphs.u8()? as i32);
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+            )
+        }
+        120..=123 => {
+            let (b1, b2, b3) = (glyphs.u8()? as i32, glyphs.u8()? as i32, glyphs.u8()? as i32);
+            (
+                with_sign(flag, (b1 << 4) + (b2 >> 4)),
+                with_sign(flag >> 1, ((b2 & 0x0f) << 8) + b3),
+            )
+        }
+        _ => {
+            let (x, y) = (glyphs.u16()? as i32, glyphs.u16()? as i32);
+            (with_sign(flag, x), with_sign(flag >> 1, y))
+        }
+    })
+}
+
+// WOFF2 §5.4, side bearings may be dropped and recovered from the glyph bounding boxes.
+fn reconstruct_hmtx(data: &[u8], num_glyphs: u16, num_h_metrics: u16, x_mins: &[i16]) -> Result<Vec<u8>, Error> {
+    let (num_glyphs, num_h_metrics) = (num_glyphs as usize, num_h_metrics as usize);
+    if num_h_metrics > num_glyphs || x_mins.len() < num_glyphs {
+        return Err(Error::Decode("WOFF2 hmtx does not match glyf"));
+    }
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+    let advances = (0..num_h_metrics).map(|_| reader.u16()).collect::<Result<Vec<_>, _>>()?;
+    let mut bearings = |range: std::ops::Range<usize>, explicit: bool| {
+        range
+            .map(|index| if explicit { reader.i16() } else { Ok(x_mins[index]) })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let proportional = bearings(0..num_h_metrics, flags & 1 == 0)?;
+    let monospaced = bearings(num_h_metrics..num_glyphs, flags & 2 == 0)?;
+
+    let mut hmtx = Vec::with_capacity(num_h_metrics * 4 + monospaced.len() * 2);
+    for (advance, bearing) in advances.iter().zip(&proportional) {
+        hmtx.extend(advance.to_be_bytes());
+        hmtx.extend(bearing.to_be_bytes());
+    }
+    monospaced.iter().for_each(|bearing| hmtx.extend(bearing.to_be_bytes()));
+    Ok(hmtx)
+}
+
+// Lays the tables out as a regular sfnt with sorted table records and fresh checksums.
+fn build_sfnt(flavor: u32, mut tables: Vec<(u32, Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| *tag);
+    if let Some((_, head)) = tables.iter_mut().find(|(tag, _)| *tag == HEAD) {
+        if let Some(adjustment) = head.get_mut(8..12) {
+            adjustment.fill(0);
+        }
+    }
+
+    let num_tables = tables.len() as u16;
+    let entry_selector = num_tables.max(1).ilog2() as u16;
+    let search_range = (1u16 << entry_selector).saturating_mul(16);
+    let mut sfnt = Vec::new();
+    sfnt.extend(flavor.to_be_bytes());
+    sfnt.extend(num_tables.to_be_bytes());
+    sfnt.extend(search_range.to_be_bytes());
+    sfnt.extend(entry_selector.to_be_bytes());
+    sfnt.extend(num_tables.saturating_mul(16).saturating_sub(search_range).to_be_bytes());
+
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for (tag, table) in &tables {
+        if *tag == HEAD {
+            head_offset = Some(offset);
+        }
+        sfnt.extend(tag.to_be_bytes());
+        sfnt.extend(checksum(table).to_be_bytes());
+        sfnt.extend((offset as u32).to_be_bytes());
+        sfnt.extend((table.len() as u32).to_be_bytes());
+        offset += (table.len() + 3) & !3;
+    }
+    for (_, table) in &tables {
+        sfnt.extend(table);
+        sfnt.resize((sfnt.len() + 3) & !3, 0);
+    }
+
+    if let Some(offset) = head_offset.filter(|offset| offset + 12 <= sfnt.len()) {
+        let adjustment = 0xB1B0AFBAu32.wrapping_sub(checksum(&sfnt));
+        sfnt[offset + 8..offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    sfnt
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn at(data: &'a [u8], offset: usize) -> Result<Self, Error> {
+        let mut reader = Self::new(data);
+        reader.skip(offset)?;
+        Ok(reader)
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + length)
+            .ok_or(Error::Decode("unexpected end of font data"))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, length: usize) -> Result<(), Error> {
+        self.bytes(length).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // WOFF2 `UIntBase128`, big endian groups of 7 bits.
+    fn base128(&mut self) -> Result<u32, Error> {
+        let mut value = 0u32;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            if (i == 0 && byte == 0x80) || value & 0xfe00_0000 != 0 {
+                return Err(Error::Decode("invalid UIntBase128"));
+            }
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::Decode("invalid UIntBase128"))
+    }
+
+    // WOFF2 `255UInt16`.
+    fn u255(&mut self) -> Result<u16, Error> {
+        match self.u8()? {
+            253 => self.u16(),
+            254 => Ok(self.u8()? as u16 + 253 * 2),
+            255 => Ok(self.u8()? as u16 + 253),
+            code => Ok(code as u16),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+    use super::*;
+
+    const TTF: &[u8] = include_bytes!("../fonts/Roboto/Roboto-Regular.ttf");
+    const WOFF_DATA: &[u8] = include_bytes!("../fonts/Roboto/Roboto-Regular.woff");
+    const WOFF2_DATA: &[u8] = include_bytes!("../fonts/Roboto/Roboto-Regular.woff2");
+
+    #[derive(Default, PartialEq, Debug)]
+    struct Path(Vec<(char, [f32; 6])>);
+
+    impl OutlineBuilder for Path {
+        fn move_to(&mut self, x: f32, y: f32) {
+            self.0.push(('M', [x, y, 0.0, 0.0, 0.0, 0.0]));
+        }
+
+        fn line_to(&mut self, x: f32, y: f32) {
+            self.0.push(('L', [x, y, 0.0, 0.0, 0.0, 0.0]));
+        }
+
+        fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+            self.0.push(('Q', [x1, y1, x, y, 0.0, 0.0]));
+        }
+
+        fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+            self.0.push(('C', [x1, y1, x2, y2, x, y]));
+        }
+
+        fn close(&mut self) {
+            self.0.push(('Z', [0.0; 6]));
+        }
+    }
+
+    fn assert_same_font(decoded: &[u8]) {
+        let expected = Face::parse(TTF, 0).unwrap();
+        let face = Face::parse(decoded, 0).unwrap();
+        assert_eq!(face.number_of_glyphs(), expected.number_of_glyphs());
+        for c in ['a', 'Z', '0', 'ß', 'Ж', '€', 'ﬁ'] {
+            assert_eq!(face.glyph_index(c), expected.glyph_index(c), "cmap lookup of {c:?}");
+        }
+        for id in (0..expected.number_of_glyphs()).map(GlyphId) {
+            assert_eq!(face.glyph_hor_advance(id), expected.glyph_hor_advance(id), "advance of {id:?}");
+            assert_eq!(face.glyph_hor_side_bearing(id), expected.glyph_hor_side_bearing(id), "bearing of {id:?}");
+            let (mut path, mut expected_path) = (Path::default(), Path::default());
+            assert_eq!(face.outline_glyph(id, &mut path), expected.outline_glyph(id, &mut expected_path), "bounding box of {id:?}");
+            assert_eq!(path, expected_path, "outline of {id:?}");
+            assert_eq!(face.glyph_bounding_box(id), expected.glyph_bounding_box(id));
+        }
+    }
+
+    #[test]
+    fn decodes_woff() {
+        assert_same_font(&decode(WOFF_DATA.into()).unwrap());
+    }
+
+    #[test]
+    fn decodes_woff2_with_transformed_glyf_and_hmtx() {
+        assert_same_font(&decode(WOFF2_DATA.into()).unwrap());
+    }
+
+    #[test]
+    fn passes_sfnt_through() {
+        assert_eq!(&*decode(TTF.into()).unwrap(), TTF);
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        for data in [WOFF_DATA, WOFF2_DATA] {
+            for length in [4, 20, 60, data.len() / 2, data.len() - 5] {
+                assert!(decode(data[..length].to_vec().into()).is_err(), "{length} bytes of {:?}", &data[..4]);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_woff2_collections() {
+        let mut data = WOFF2_DATA.to_vec();
+        data[4..8].copy_from_slice(b"ttcf");
+        assert!(matches!(decode(data.into()), Err(Error::Decode(_))));
+    }
+
+    #[test]
+    fn rejects_fonts_without_tables() {
+        for font in [WOFF_DATA, WOFF2_DATA] {
+            let mut data = font.to_vec();
+            data[12..14].fill(0);
+            assert!(matches!(decode(data.into()), Err(Error::Decode(_))), "{:?}", &font[..4]);
+        }
+    }
+
+    #[test]
+    fn table_lengths_are_not_trusted() {
+        // One zlib compressed table claiming to inflate to 4 GiB.
+        let mut compressed = Vec::new();
+        let mut encoder = flate2::write::ZlibEncoder::new(&mut compressed, flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &[0; 64]).unwrap();
+        encoder.finish().unwrap();
+        let mut data = Vec::new();
+        data.extend(b"wOFF");
+        data.extend(0x00010000u32.to_be_bytes());
+        data.extend(((64 + compressed.len()) as u32).to_be_bytes());
+        data.extend(1u16.to_be_bytes());
+        data.resize(44, 0);
+        data.extend(u32::from_be_bytes(*b"head").to_be_bytes());
+        data.extend(64u32.to_be_bytes());
+        data.extend((compressed.len() as u32).to_be_bytes());
+        data.extend(u32::MAX.to_be_bytes());
+        data.extend(0u32.to_be_bytes());
+        data.extend(&compressed);
+        assert!(matches!(decode(data.into()), Err(Error::Decode(_))));
+    }
+
+    #[test]
+    fn reads_variable_length_integers() {
+        assert_eq!(Reader::new(&[0x3f]).base128().unwrap(), 63);
+        assert_eq!(Reader::new(&[0x81, 0x00]).base128().unwrap(), 128);
+        assert!(Reader::new(&[0x80, 0x01]).base128().is_err());
+        assert!(Reader::new(&[0xff, 0xff, 0xff, 0xff, 0x7f]).base128().is_err());
+        assert_eq!(Reader::new(&[252]).u255().unwrap(), 252);
+        assert_eq!(Reader::new(&[255, 0]).u255().unwrap(), 253);
+        assert_eq!(Reader::new(&[254, 0]).u255().unwrap(), 506);
+        assert_eq!(Reader::new(&[253, 0x12, 0x34]).u255().unwrap(), 0x1234);
+    }
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use winit::{
     dpi::PhysicalSize,
     error::OsError,
-    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
@@ -11,9 +11,17 @@ use winit::{
 pub mod font;
 mod renderer;
 
+pub use renderer::{
+    attributed::{AttributedText, Attributes},
+    layout::{Align, Layout, VerticalAlign},
+    typeset::{Bounds, LineMetrics, TextLayout, TextMetrics, Typesetter},
+};
+
 #[derive(Default, Debug, Clone)]
 pub struct Application<'a> {
     pub window_config: WindowConfig<'a>,
+    pub fonts: Vec<font::Source>,
+    pub font_loader: font::FontLoader,
 }
 
 impl<'a> Application<'a> {
@@ -34,11 +42,25 @@ impl<'a> Application<'a> {
         let mut render_state = renderer::State::new(window).await;
         let mut text_state = renderer::text::TextState::new(&render_state);
 
-        text_state.draw(30, 50, "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.", Arc::new(font::Font::DEFAULT));
+        let mut fonts = Vec::with_capacity(self.fonts.len());
+        for src in &self.fonts {
+            match self.font_loader.load(src).await {
+                Ok(font) => fonts.push(Arc::new(font)),
+                Err(err) => log::error!("Failed to load font {src:?}: {err}"),
+            }
+        }
+        let font = fonts.first().cloned().unwrap_or_else(|| Arc::new(font::Font::DEFAULT));
+
+        let block = text_state.draw(30, 50, "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.", font, &font::FontFeatures::default(), &Layout::new());
+        text_state.set_selection_color([255, 220, 120, 255]);
         //text_state.draw(30, 600, "“Hello, World!” gg++-- ÜÜÜ###", Arc::new(font::Font::DEFAULT));
         //text_state.draw(30, 30, "ن بنشوة اللحظة الهائمون في رغباتهم فلا يدركون ما يعقبها من الألم و", Arc::new(font::Font::CAIRO));
         //text_state.draw(30, 600, "\"Hello, World!\" ++--gpq", Arc::new(font::Font::MONOSPACE));
 
+        // Screen y grows upwards, cursor positions downwards.
+        let mut cursor = (0.0, 0.0);
+        // Where a drag selection started.
+        let mut anchor = None;
         event_loop.run(move |event, _, control_flow| {
             *control_flow = ControlFlow::Wait;
 
@@ -57,12 +79,42 @@ impl<'a> Application<'a> {
                             },
                         ..
                     } => *control_flow = ControlFlow::Exit,
+                    WindowEvent::CursorMoved { position, .. } => {
+                        cursor = (position.x as f32, render_state.size.height as f32 - position.y as f32);
+                        if let (Some(anchor), Some(layout)) = (anchor, text_state.text_layout(block)) {
+                            let index = layout.hit_test(cursor.0, cursor.1);
+                            text_state.select(block, anchor..index);
+                        }
+                    }
+                    WindowEvent::MouseInput {
+                        state,
+                        button: MouseButton::Left,
+                        ..
+                    } => match state {
+                        ElementState::Pressed => {
+                            if let Some(layout) = text_state.text_layout(block) {
+                                let index = layout.hit_test(cursor.0, cursor.1);
+                                log::debug!("Clicked at byte {index}, caret {:?}", layout.caret(index));
+                                anchor = Some(index);
+                                text_state.select(block, index..index);
+                            }
+                        }
+                        ElementState::Released => {
+                            anchor = None;
+                            if let Some(text) = text_state.selected_text(block) {
+                                log::debug!("Selected {text:?}");
+                            }
+                        }
+                    },
                     WindowEvent::Resized(physical_size) => {
                         render_state.resize(*physical_size);
+                        text_state.resize(render_state.size.width);
                     }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                    WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size } => {
                         // new_inner_size is &&mut so we have to dereference it twice
                         render_state.resize(**new_inner_size);
+                        text_state.rescale(*scale_factor);
+                        text_state.resize(render_state.size.width);
                     }
                     _ => {}
                 },
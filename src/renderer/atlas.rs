@@ -25,6 +25,10 @@ impl FontAtlas {
 
     pub(crate) const SCALE_FACTOR: f64 = 1.0 / 8.0;
     pub(crate) const TEXTURE_SCALE: u32 = 4096;
+    // Distance the signed distance field spans, in font units.
+    pub(crate) const RANGE: f64 = 64.0;
+    // Border around every glyph in font units, leaves room for synthetic bold to grow into.
+    pub(crate) const PADDING: f64 = Self::RANGE / 2.0;
     const THREAD_COUNT: usize = 8;
 
     // TODO: Optimize
@@ -45,47 +49,49 @@ impl FontAtlas {
             ..Default::default()
         };
     
-        let face = Arc::new(ttf_parser::Face::parse(font.data, 0)?);
+        // Validate once up front, the workers re-parse from the shared bytes since `Face` borrows them.
+        font.face()?;
 
         let (tx, rx) = mpsc::channel();
         
         for glyph_id in ids {
-            let face = face.clone();
+            let font = font.clone();
             let msdf_config = msdf_config.clone();
             let tx = tx.clone();
             pool.execute(move || {
-                let shape = face.load_shape(glyph_id).unwrap_or(face.load_shape(GlyphId::default()).unwrap());
-                    let shape = shape.color_edges_ink_trap(3.0);
-                    let ttf_parser::Rect {
-                        x_min,
-                        y_min,
-                        x_max,
-                        y_max,
-                    } = face.glyph_bounding_box(glyph_id).unwrap_or(
-                        face.glyph_bounding_box(ttf_parser::GlyphId::default())
-                            .expect("THIS IS THE POINT OF DEATH"),
-                    );
-                    let glyph_projection = msdf::Projection {
-                        scale: mint::Vector2 {
-                            x: Self::SCALE_FACTOR,
-                            y: Self::SCALE_FACTOR,
-                        },
-                        translation: mint::Vector2 {
-                            x: x_min as f64 * -1.0,
-                            y: y_min as f64 * -1.0,
-                        },
-                    };
-                    let img = shape
-                        .generate_msdf(
-                            ((x_max - x_min) as f64 * Self::SCALE_FACTOR).ceil() as u32,
-                            ((y_max - y_min) as f64 * Self::SCALE_FACTOR).ceil() as u32,
-                            64.0,
-                            &glyph_projection,
-                            &msdf_config,
-                        )
-                        .to_image();
-    
-                    tx.send((glyph_id, img)).expect(":.(");
+                let face = font.face().unwrap();
+                // Glyphs without an outline have nothing to render and are left out of the atlas.
+                let (Some(shape), Some(bounds)) = (face.load_shape(glyph_id), face.glyph_bounding_box(glyph_id)) else {
+                    return;
+                };
+                let shape = shape.color_edges_ink_trap(3.0);
+                let ttf_parser::Rect {
+                    x_min,
+                    y_min,
+                    x_max,
+                    y_max,
+                } = bounds;
+                let glyph_projection = msdf::Projection {
+                    scale: mint::Vector2 {
+                        x: Self::SCALE_FACTOR,
+                        y: Self::SCALE_FACTOR,
+                    },
+                    translation: mint::Vector2 {
+                        x: x_min as f64 * -1.0 + Self::PADDING,
+                        y: y_min as f64 * -1.0 + Self::PADDING,
+                    },
+                };
+                let img = shape
+                    .generate_msdf(
+                        (((x_max - x_min) as f64 + 2.0 * Self::PADDING) * Self::SCALE_FACTOR).ceil() as u32,
+                        (((y_max - y_min) as f64 + 2.0 * Self::PADDING) * Self::SCALE_FACTOR).ceil() as u32,
+                        Self::RANGE,
+                        &glyph_projection,
+                        &msdf_config,
+                    )
+                    .to_image();
+
+                tx.send((glyph_id, img)).expect(":.(");
             });
         }
         pool.join();
@@ -126,6 +132,11 @@ impl FontAtlas {
         })
     }
 
+    /// Whether a glyph has an outline to render, blanks and bitmap only glyphs have none.
+    pub(crate) fn has_outline(face: &ttf_parser::Face, glyph_id: GlyphId) -> bool {
+        face.outline_glyph(glyph_id, &mut NoOutline).is_some()
+    }
+
     pub fn desc<'a>() -> wgpu::TextureDescriptor<'a> {
         wgpu::TextureDescriptor {
             label: Some("Font Atlas"),
@@ -146,6 +157,17 @@ impl FontAtlas {
 
 
 
+// Discards the outline, only whether there is one matters.
+struct NoOutline;
+
+impl ttf_parser::OutlineBuilder for NoOutline {
+    fn move_to(&mut self, _: f32, _: f32) {}
+    fn line_to(&mut self, _: f32, _: f32) {}
+    fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {}
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+    fn close(&mut self) {}
+}
+
 struct ImageExporterF32S8A<T: texture_packer::texture::Texture<Pixel = image::Rgba<f32>>> {
     t: std::marker::PhantomData<T>,
 }
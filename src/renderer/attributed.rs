@@ -0,0 +1,220 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::font::{Font, FontFeatures, FontSize};
+
+/// Attributes of a span of [`AttributedText`], unset ones are taken from the draw call.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes {
+    /// Fallback chain of the span, e.g. a bold face from [`crate::font::FontDatabase::query`] for a bold word.
+    pub fonts: Option<Vec<Arc<Font>>>,
+    pub size: Option<FontSize>,
+    /// Straight RGBA, black if no span sets it.
+    pub color: Option<[u8; 4]>,
+    /// Added to the features of the draw call, features without a range cover the span.
+    pub features: FontFeatures,
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_font(self, font: Arc<Font>) -> Self {
+        self.with_fonts(vec![font])
+    }
+
+    pub fn with_fonts(mut self, fonts: Vec<Arc<Font>>) -> Self {
+        self.fonts = Some(fonts);
+        self
+    }
+
+    pub fn with_size(mut self, size: FontSize) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_color(mut self, color: [u8; 4]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn with_features(mut self, features: FontFeatures) -> Self {
+        self.features = features;
+        self
+    }
+
+    // Lays `other` over `self`, set attributes of `other` win and features add up.
+    fn merge(&mut self, other: &Attributes, range: &Range<usize>) {
+        if other.fonts.is_some() {
+            self.fonts = other.fonts.clone();
+        }
+        self.size = other.size.or(self.size);
+        self.color = other.color.or(self.color);
+        for feature in other.features.features() {
+            let mut feature = feature.clone();
+            feature.range = Some(feature.range.unwrap_or_else(|| range.clone()));
+            self.features = std::mem::take(&mut self.features).with_feature(feature);
+        }
+    }
+}
+
+/// Text with [`Attributes`] on byte ranges, laid out as one block with shared baselines,
+/// line breaking and bidi.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttributedText {
+    text: String,
+    spans: Vec<(Range<usize>, Attributes)>,
+}
+
+impl AttributedText {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            spans: Vec::new(),
+        }
+    }
+
+    /// Appends `text` with `attributes`.
+    pub fn with_span(mut self, text: &str, attributes: Attributes) -> Self {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.spans.push((start..self.text.len(), attributes));
+        self
+    }
+
+    /// Sets `attributes` on a byte range of the text, over the spans set before. Panics if the
+    /// range is reversed or doesn't fall on char boundaries, like slicing a `str` would.
+    pub fn with_attributes(mut self, range: Range<usize>, attributes: Attributes) -> Self {
+        assert!(range.start <= range.end, "span {range:?} is reversed");
+        assert!(
+            self.text.is_char_boundary(range.start) && self.text.is_char_boundary(range.end),
+            "span {range:?} is out of bounds or not on char boundaries"
+        );
+        self.spans.push((range, attributes));
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Splits the text at every span boundary, each run carries the merged attributes of the
+    // spans covering it.
+    pub(crate) fn runs(&self) -> Vec<(Range<usize>, Attributes)> {
+        let mut boundaries = vec![0, self.text.len()];
+        for (range, _) in &self.spans {
+            boundaries.extend([range.start, range.end]);
+        }
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        boundaries
+            .windows(2)
+            .map(|window| {
+                let run = window[0]..window[1];
+                let mut attributes = Attributes::default();
+                for (range, span) in &self.spans {
+                    if range.start <= run.start && run.end <= range.end {
+                        attributes.merge(span, range);
+                    }
+                }
+                (run, attributes)
+            })
+            .collect()
+    }
+}
+
+impl From<&str> for AttributedText {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Feature;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    fn colors(text: &AttributedText) -> Vec<(Range<usize>, Option<[u8; 4]>)> {
+        text.runs().into_iter().map(|(range, attributes)| (range, attributes.color)).collect()
+    }
+
+    #[test]
+    fn runs_split_at_every_span_boundary() {
+        let text = AttributedText::new("hello world")
+            .with_attributes(2..7, Attributes::new().with_color(RED))
+            .with_attributes(4..9, Attributes::new().with_size(FontSize::Pixels(20.0)));
+        let runs = text.runs();
+        assert_eq!(runs.iter().map(|(range, _)| range.clone()).collect::<Vec<_>>(), [0..2, 2..4, 4..7, 7..9, 9..11]);
+        assert_eq!(runs.iter().map(|(_, attributes)| (attributes.color, attributes.size)).collect::<Vec<_>>(), [
+            (None, None),
+            (Some(RED), None),
+            (Some(RED), Some(FontSize::Pixels(20.0))),
+            (None, Some(FontSize::Pixels(20.0))),
+            (None, None),
+        ]);
+    }
+
+    #[test]
+    fn later_spans_win_where_they_overlap() {
+        let text = AttributedText::new("overlapping")
+            .with_attributes(0..5, Attributes::new().with_color(RED))
+            .with_attributes(3..8, Attributes::new().with_color(BLUE));
+        assert_eq!(colors(&text), [(0..3, Some(RED)), (3..5, Some(BLUE)), (5..8, Some(BLUE)), (8..11, None)]);
+    }
+
+    #[test]
+    fn nested_spans_only_override_what_they_set() {
+        let font = Arc::new(Font::DEFAULT);
+        let text = AttributedText::new("outer inner")
+            .with_attributes(0..11, Attributes::new().with_color(RED).with_font(font.clone()))
+            .with_attributes(6..11, Attributes::new().with_color(BLUE));
+        let runs = text.runs();
+        assert_eq!(colors(&text), [(0..6, Some(RED)), (6..11, Some(BLUE))]);
+        assert!(runs.iter().all(|(_, attributes)| attributes.fonts == Some(vec![font.clone()])));
+    }
+
+    #[test]
+    fn span_features_are_scoped_to_the_span() {
+        let text = AttributedText::new("a -> b")
+            .with_span("", Attributes::new())
+            .with_attributes(2..4, Attributes::new().with_features(FontFeatures::new().with_ligatures(false)))
+            .with_attributes(0..6, Attributes::new().with_features(FontFeatures::new().with_range(*b"smcp", 1, 5..6)));
+        let (_, arrow) = text.runs().into_iter().find(|(range, _)| *range == (2..4)).unwrap();
+        let features = arrow
+            .features
+            .features()
+            .iter()
+            .map(|feature| (feature.tag, feature.value, feature.range.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(features, [
+            (Feature::STANDARD_LIGATURES, 0, Some(2..4)),
+            (Feature::CONTEXTUAL_LIGATURES, 0, Some(2..4)),
+            (Feature::SMALL_CAPS, 1, Some(5..6)),
+        ]);
+    }
+
+    #[test]
+    fn appended_spans_cover_their_text() {
+        let text = AttributedText::new("plain ")
+            .with_span("red", Attributes::new().with_color(RED))
+            .with_span(" blue", Attributes::new().with_color(BLUE));
+        assert_eq!(text.text(), "plain red blue");
+        assert_eq!(colors(&text), [(0..6, None), (6..9, Some(RED)), (9..14, Some(BLUE))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "reversed")]
+    fn reversed_spans_are_rejected() {
+        let (start, end) = (2, 1);
+        let _ = AttributedText::new("abc").with_attributes(start..end, Attributes::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "char boundaries")]
+    fn spans_inside_a_char_are_rejected() {
+        let _ = AttributedText::new("é").with_attributes(0..1, Attributes::new());
+    }
+}
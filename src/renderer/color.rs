@@ -0,0 +1,268 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use ab_glyph_rasterizer::{point, Point, Rasterizer};
+use texture_packer::{
+    exporter::{Exporter, ImageExporter},
+    TexturePacker,
+};
+use ttf_parser::{GlyphId, RasterImageFormat};
+
+use crate::{
+    font::{self, color},
+    renderer::UVRect,
+};
+
+/// Placement of a color glyph, in font units relative to the glyph origin.
+pub(crate) struct ColorGlyph {
+    pub uv: UVRect,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// A color glyph, with the text color it was baked in if it has layers in the text color.
+pub(crate) type ColorKey = (GlyphId, Option<[u8; 4]>);
+
+/// RGBA atlas for the glyphs the MSDF atlas can't represent: COLR layered glyphs and CBDT/sbix bitmaps.
+/// COLR glyphs with layers in the text color are baked once per color they are drawn in.
+pub(crate) struct ColorAtlas {
+    pub map: HashMap<ColorKey, ColorGlyph>,
+    pub texture: image::RgbaImage,
+}
+
+impl ColorAtlas {
+    // Size COLR glyphs are rasterized at and bitmap strikes are picked for.
+    const PIXELS_PER_EM: u16 = 128;
+
+    /// Bakes `ids`, layers in the text color once for each of the `foregrounds`.
+    pub fn new(ids: Vec<GlyphId>, foregrounds: &HashSet<[u8; 4]>, font: Arc<font::Font>) -> Result<Self, ttf_parser::FaceParsingError> {
+        let face = font.face()?;
+        let packer_config = texture_packer::TexturePackerConfig {
+            max_width: super::atlas::FontAtlas::TEXTURE_SCALE,
+            max_height: super::atlas::FontAtlas::TEXTURE_SCALE,
+            allow_rotation: false,
+            border_padding: 0,
+            texture_padding: 1,
+            ..Default::default()
+        };
+
+        let mut placements = HashMap::new();
+        let mut packer = TexturePacker::new_skyline(packer_config);
+        for glyph_id in ids {
+            for (key, image, placement) in Self::render(&face, glyph_id, foregrounds) {
+                match packer.pack_own(key, image) {
+                    Ok(()) => {
+                        placements.insert(key, placement);
+                    }
+                    Err(err) => log::warn!("Color glyph {glyph_id:?} does not fit into the atlas: {err:?}"),
+                }
+            }
+        }
+
+        let texture = ImageExporter::export(&packer)
+            .map(|image| image.to_rgba8())
+            .unwrap_or_default();
+
+        let map = packer
+            .get_frames()
+            .iter()
+            .filter_map(|(key, frame)| {
+                let texture_packer::Rect { x, y, w, h } = frame.frame;
+                let [x_min, y_min, width, height] = placements.remove(key)?;
+                Some((
+                    *key,
+                    ColorGlyph {
+                        uv: UVRect {
+                            u: x as f32 / texture.width() as f32,
+                            v: y as f32 / texture.height() as f32,
+                            w: w as f32 / texture.width() as f32,
+                            h: h as f32 / texture.height() as f32,
+                        },
+                        x: x_min,
+                        y: y_min,
+                        width,
+                        height,
+                    },
+                ))
+            })
+            .collect();
+
+        Ok(Self { map, texture })
+    }
+
+    /// The baked glyph drawn in the text color `foreground`.
+    pub fn get(&self, glyph_id: GlyphId, foreground: [u8; 4]) -> Option<&ColorGlyph> {
+        self.map
+            .get(&(glyph_id, Some(foreground)))
+            .or_else(|| self.map.get(&(glyph_id, None)))
+    }
+
+    // The images of a color glyph together with the rect they cover in font units, one per
+    // foreground if it has layers in the text color.
+    fn render(face: &ttf_parser::Face, glyph_id: GlyphId, foregrounds: &HashSet<[u8; 4]>) -> Vec<(ColorKey, image::RgbaImage, [f32; 4])> {
+        let layers = match color::layers(face, glyph_id) {
+            Some(layers) => layers,
+            None => return Self::render_bitmap(face, glyph_id)
+                .map(|(image, placement)| ((glyph_id, None), image, placement))
+                .into_iter()
+                .collect(),
+        };
+        match layers.iter().any(|layer| layer.color.is_none()) {
+            true => foregrounds
+                .iter()
+                .filter_map(|foreground| {
+                    let (image, placement) = Self::render_layers(face, &layers, Some(*foreground))?;
+                    Some(((glyph_id, Some(*foreground)), image, placement))
+                })
+                .collect(),
+            false => Self::render_layers(face, &layers, None)
+                .map(|(image, placement)| ((glyph_id, None), image, placement))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn render_bitmap(face: &ttf_parser::Face, glyph_id: GlyphId) -> Option<(image::RgbaImage, [f32; 4])> {
+        let raster = color::raster_image(face, glyph_id, Self::PIXELS_PER_EM)?;
+        let image = match raster.format {
+            RasterImageFormat::PNG => image::load_from_memory_with_format(raster.data, image::ImageFormat::Png)
+                .map_err(|err| log::warn!("Failed to decode bitmap of glyph {glyph_id:?}: {err}"))
+                .ok()?
+                .to_rgba8(),
+            RasterImageFormat::BitmapPremulBgra32 => {
+                let pixels = raster
+                    .data
+                    .chunks_exact(4)
+                    .flat_map(|bgra| {
+                        let unpremultiply = |c: u8| match bgra[3] {
+                            0 => 0,
+                            a => (c as u32 * 255 / a as u32).min(255) as u8,
+                        };
+                        [unpremultiply(bgra[2]), unpremultiply(bgra[1]), unpremultiply(bgra[0]), bgra[3]]
+                    })
+                    .collect();
+                image::RgbaImage::from_raw(raster.width as u32, raster.height as u32, pixels)?
+            }
+            _ => return None,
+        };
+        // Bitmap metrics are in pixels of the strike, the offset is the bottom left corner.
+        let units_per_pixel = face.units_per_em() as f32 / raster.pixels_per_em as f32;
+        let placement = [
+            raster.x as f32 * units_per_pixel,
+            raster.y as f32 * units_per_pixel,
+            image.width() as f32 * units_per_pixel,
+            image.height() as f32 * units_per_pixel,
+        ];
+        Some((image, placement))
+    }
+
+    fn render_layers(face: &ttf_parser::Face, layers: &[color::ColorLayer], foreground: Option<[u8; 4]>) -> Option<(image::RgbaImage, [f32; 4])> {
+        let bounds = layers
+            .iter()
+            .filter_map(|layer| face.glyph_bounding_box(layer.glyph_id))
+            .reduce(|a, b| ttf_parser::Rect {
+                x_min: a.x_min.min(b.x_min),
+                y_min: a.y_min.min(b.y_min),
+                x_max: a.x_max.max(b.x_max),
+                y_max: a.y_max.max(b.y_max),
+            })?;
+        let scale = Self::PIXELS_PER_EM as f32 / face.units_per_em() as f32;
+        let width = (bounds.width() as f32 * scale).ceil().max(1.0) as usize;
+        let height = (bounds.height() as f32 * scale).ceil().max(1.0) as usize;
+
+        // Composited in premultiplied alpha, layer over layer.
+        let mut pixels = vec![[0.0f32; 4]; width * height];
+        for layer in layers {
+            let mut outline = LayerOutline {
+                rasterizer: Rasterizer::new(width, height),
+                scale,
+                x_min: bounds.x_min as f32,
+                y_max: bounds.y_max as f32,
+                start: point(0.0, 0.0),
+                last: point(0.0, 0.0),
+            };
+            if face.outline_glyph(layer.glyph_id, &mut outline).is_none() {
+                continue;
+            }
+            let Some(color) = layer.color.or(foreground) else {
+                continue;
+            };
+            let [r, g, b, a] = color.map(|c| c as f32 / 255.0);
+            outline.rasterizer.for_each_pixel_2d(|x, y, coverage| {
+                let alpha = a * coverage.min(1.0);
+                let pixel = &mut pixels[y as usize * width + x as usize];
+                for (channel, value) in pixel.iter_mut().zip([r * alpha, g * alpha, b * alpha, alpha]) {
+                    *channel = value + *channel * (1.0 - alpha);
+                }
+            });
+        }
+
+        let pixels = pixels
+            .into_iter()
+            .flat_map(|[r, g, b, a]| {
+                let straight = |c: f32| if a > 0.0 { (c / a * 255.0).round().min(255.0) as u8 } else { 0 };
+                [straight(r), straight(g), straight(b), (a * 255.0).round() as u8]
+            })
+            .collect();
+        let image = image::RgbaImage::from_raw(width as u32, height as u32, pixels)?;
+        let placement = [
+            bounds.x_min as f32,
+            bounds.y_min as f32,
+            width as f32 / scale,
+            height as f32 / scale,
+        ];
+        Some((image, placement))
+    }
+}
+
+// Feeds a layer outline into the rasterizer, flipping y so row 0 is the top of the glyph.
+struct LayerOutline {
+    rasterizer: Rasterizer,
+    scale: f32,
+    x_min: f32,
+    y_max: f32,
+    start: Point,
+    last: Point,
+}
+
+impl LayerOutline {
+    fn map(&self, x: f32, y: f32) -> Point {
+        point((x - self.x_min) * self.scale, (self.y_max - y) * self.scale)
+    }
+}
+
+impl ttf_parser::OutlineBuilder for LayerOutline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.map(x, y);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to = self.map(x, y);
+        self.rasterizer.draw_line(self.last, to);
+        self.last = to;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let to = self.map(x, y);
+        self.rasterizer.draw_quad(self.last, self.map(x1, y1), to);
+        self.last = to;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let to = self.map(x, y);
+        self.rasterizer.draw_cubic(self.last, self.map(x1, y1), self.map(x2, y2), to);
+        self.last = to;
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.rasterizer.draw_line(self.last, self.start);
+        }
+        self.last = self.start;
+    }
+}
@@ -0,0 +1,285 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::typeset::{Bounds, Line, TextLayout};
+
+/// Where a grapheme cluster ended up on its line, in pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GraphemeBox {
+    pub range: Range<usize>,
+    pub x_min: f32,
+    pub x_max: f32,
+    pub rtl: bool,
+}
+
+impl GraphemeBox {
+    // The edge the caret sits at before the grapheme, the other edge is after it.
+    fn leading(&self) -> f32 {
+        if self.rtl { self.x_max } else { self.x_min }
+    }
+
+    fn trailing(&self) -> f32 {
+        if self.rtl { self.x_min } else { self.x_max }
+    }
+}
+
+/// The grapheme clusters of a laid out line in visual order. Glyphs are mapped to text through
+/// their harfbuzz cluster, a ligature's advance is split evenly between the graphemes it covers.
+pub(crate) fn graphemes(text: &str, line: &Line) -> Vec<GraphemeBox> {
+    let mut boxes = Vec::new();
+    for sequence in &line.sequences {
+        // Glyphs of a cluster are adjacent, with their advances summed up.
+        let mut clusters: Vec<(usize, f32, f32)> = Vec::new();
+        let mut cursor = sequence.x;
+        for glyph in &sequence.glyphs {
+            let advance = glyph.x_advance * sequence.scale;
+            match clusters.last_mut() {
+                Some((cluster, _, x_max)) if *cluster == glyph.cluster => *x_max += advance,
+                _ => clusters.push((glyph.cluster, cursor, cursor + advance)),
+            }
+            cursor += advance;
+        }
+
+        // A cluster ends where the logically next one starts.
+        let mut starts = clusters.iter().map(|(cluster, _, _)| *cluster).collect::<Vec<_>>();
+        starts.sort_unstable();
+        starts.dedup();
+        for (cluster, x_min, x_max) in clusters {
+            let end = starts
+                .iter()
+                .find(|start| **start > cluster)
+                .copied()
+                .unwrap_or(sequence.range.end);
+            let mut graphemes = text[cluster..end]
+                .grapheme_indices(true)
+                .map(|(offset, grapheme)| cluster + offset..cluster + offset + grapheme.len())
+                .collect::<Vec<_>>();
+            if sequence.rtl {
+                graphemes.reverse();
+            }
+            let width = (x_max - x_min) / graphemes.len().max(1) as f32;
+            for (index, range) in graphemes.into_iter().enumerate() {
+                boxes.push(GraphemeBox {
+                    range,
+                    x_min: x_min + index as f32 * width,
+                    x_max: x_min + (index + 1) as f32 * width,
+                    rtl: sequence.rtl,
+                });
+            }
+        }
+    }
+    boxes
+}
+
+impl TextLayout {
+    /// The byte index of the caret position closest to `(x, y)`, in the coordinates the text was
+    /// laid out in. Clicks on the left half of a grapheme land before it in reading order.
+    pub fn hit_test(&self, x: f32, y: f32) -> usize {
+        // The line whose vertical extent is closest to y.
+        let distance = |index: &usize| {
+            let line = &self.lines[*index];
+            let (bottom, top) = (line.baseline + line.descender, line.baseline + line.ascender);
+            if y > top { y - top } else if y < bottom { bottom - y } else { 0.0 }
+        };
+        let Some(index) = (0..self.lines.len()).min_by(|a, b| distance(a).total_cmp(&distance(b))) else {
+            return 0;
+        };
+        let graphemes = &self.graphemes[index];
+        let (Some(first), Some(last)) = (graphemes.first(), graphemes.last()) else {
+            return self.lines[index].range.start;
+        };
+
+        if x <= first.x_min {
+            return if first.rtl { first.range.end } else { first.range.start };
+        }
+        let grapheme = graphemes
+            .iter()
+            .find(|grapheme| x < grapheme.x_max)
+            .unwrap_or(last);
+        let before = x < (grapheme.x_min + grapheme.x_max) / 2.0;
+        match before != grapheme.rtl {
+            true => grapheme.range.start,
+            false => grapheme.range.end,
+        }
+    }
+
+    /// A zero width rectangle spanning the line height at the caret position before the grapheme
+    /// at byte `index`. An index at the end of a wrapped line puts the caret at the start of the next.
+    pub fn caret(&self, index: usize) -> Option<Bounds> {
+        let line = self.lines.iter().rposition(|line| line.range.start <= index)?;
+        let metrics = &self.lines[line];
+        let graphemes = &self.graphemes[line];
+        let x = graphemes
+            .iter()
+            .find(|grapheme| grapheme.range.start == index)
+            .map(GraphemeBox::leading)
+            .or_else(|| {
+                graphemes
+                    .iter()
+                    .find(|grapheme| grapheme.range.end == index)
+                    .map(GraphemeBox::trailing)
+            })
+            .unwrap_or(metrics.x);
+        Some(Bounds {
+            x_min: x,
+            y_min: metrics.baseline + metrics.descender,
+            x_max: x,
+            y_max: metrics.baseline + metrics.ascender,
+        })
+    }
+
+    /// Highlight rectangles of the byte range `selection`, one per visually contiguous piece.
+    /// A selection is split where lines wrap and where bidi reordering separates it.
+    pub fn selection_rects(&self, selection: Range<usize>) -> Vec<Bounds> {
+        let mut rects = Vec::new();
+        for (line, graphemes) in self.lines.iter().zip(&self.graphemes) {
+            let mut current: Option<Bounds> = None;
+            for grapheme in graphemes {
+                let selected = grapheme.range.start < selection.end && selection.start < grapheme.range.end;
+                match (selected, current.as_mut()) {
+                    (true, Some(rect)) => rect.x_max = grapheme.x_max,
+                    (true, None) => {
+                        current = Some(Bounds {
+                            x_min: grapheme.x_min,
+                            y_min: line.baseline + line.descender,
+                            x_max: grapheme.x_max,
+                            y_max: line.baseline + line.ascender,
+                        })
+                    }
+                    (false, _) => rects.extend(current.take()),
+                }
+            }
+            rects.extend(current);
+        }
+        rects
+    }
+
+    /// The text of `selection`, `None` if it doesn't fall on char boundaries.
+    pub fn selected_text(&self, selection: Range<usize>) -> Option<&str> {
+        self.text().get(selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ttf_parser::GlyphId;
+
+    use super::*;
+    use crate::{font::Font, renderer::{shaper::Glyph, typeset::GlyphSequence}};
+
+    // Glyphs in visual order as (cluster, advance), at a scale of one pixel per font unit.
+    fn sequence(x: f32, baseline: f32, glyphs: &[(usize, f32)], range: Range<usize>, rtl: bool) -> GlyphSequence {
+        GlyphSequence {
+            glyphs: glyphs
+                .iter()
+                .map(|(cluster, advance)| Glyph {
+                    glyph_id: GlyphId(0),
+                    cluster: *cluster,
+                    x_advance: *advance,
+                    y_advance: 0.0,
+                    x_offset: 0.0,
+                    y_offset: 0.0,
+                })
+                .collect(),
+            x,
+            y: baseline,
+            font: Arc::new(Font::DEFAULT),
+            scale: 1.0,
+            color: [0, 0, 0, 255],
+            range,
+            rtl,
+        }
+    }
+
+    fn line(range: Range<usize>, baseline: f32, sequences: Vec<GlyphSequence>) -> Line {
+        let x = sequences.first().map_or(0.0, |sequence| sequence.x);
+        Line {
+            range,
+            width: sequences.iter().map(GlyphSequence::advance).sum(),
+            sequences,
+            x,
+            baseline,
+            ascender: 10.0,
+            descender: -3.0,
+            line_gap: 0.0,
+        }
+    }
+
+    fn caret_x(layout: &TextLayout, index: usize) -> f32 {
+        layout.caret(index).unwrap().x_min
+    }
+
+    #[test]
+    fn left_to_right() {
+        let text = "ab";
+        let layout = TextLayout::new(text, &[line(0..2, 0.0, vec![sequence(0.0, 0.0, &[(0, 10.0), (1, 10.0)], 0..2, false)])]);
+        assert_eq!(layout.hit_test(-5.0, 0.0), 0);
+        assert_eq!(layout.hit_test(4.0, 0.0), 0);
+        assert_eq!(layout.hit_test(6.0, 0.0), 1);
+        assert_eq!(layout.hit_test(25.0, 0.0), 2);
+        assert_eq!([0, 1, 2].map(|index| caret_x(&layout, index)), [0.0, 10.0, 20.0]);
+    }
+
+    #[test]
+    fn right_to_left_runs_are_mirrored() {
+        // Harfbuzz puts the glyphs of a right-to-left run in visual order, the last character first.
+        let text = "אב";
+        let layout = TextLayout::new(text, &[line(0..4, 0.0, vec![sequence(0.0, 0.0, &[(2, 10.0), (0, 10.0)], 0..4, true)])]);
+        assert_eq!(layout.hit_test(18.0, 0.0), 0);
+        assert_eq!(layout.hit_test(12.0, 0.0), 2);
+        assert_eq!(layout.hit_test(8.0, 0.0), 2);
+        assert_eq!(layout.hit_test(2.0, 0.0), 4);
+        assert_eq!([0, 2, 4].map(|index| caret_x(&layout, index)), [20.0, 10.0, 0.0]);
+    }
+
+    #[test]
+    fn ligatures_are_split_between_their_graphemes() {
+        let text = "fix";
+        let layout = TextLayout::new(text, &[line(0..3, 0.0, vec![sequence(0.0, 0.0, &[(0, 20.0), (2, 10.0)], 0..3, false)])]);
+        let graphemes = &layout.graphemes[0];
+        assert_eq!(graphemes.iter().map(|g| (g.range.clone(), g.x_min, g.x_max)).collect::<Vec<_>>(), [(0..1, 0.0, 10.0), (1..2, 10.0, 20.0), (2..3, 20.0, 30.0)]);
+        assert_eq!(layout.hit_test(12.0, 0.0), 1);
+        assert_eq!(caret_x(&layout, 1), 10.0);
+    }
+
+    #[test]
+    fn carets_at_a_wrap_go_to_the_next_line() {
+        let text = "ab cd";
+        let layout = TextLayout::new(
+            text,
+            &[
+                line(0..3, 0.0, vec![sequence(0.0, 0.0, &[(0, 10.0), (1, 10.0), (2, 5.0)], 0..3, false)]),
+                line(3..5, -20.0, vec![sequence(0.0, -20.0, &[(3, 10.0), (4, 10.0)], 3..5, false)]),
+            ],
+        );
+        let caret = layout.caret(3).unwrap();
+        assert_eq!((caret.x_min, caret.y_min, caret.y_max), (0.0, -23.0, -10.0));
+        assert_eq!(caret_x(&layout, 2), 20.0);
+        assert_eq!(layout.hit_test(100.0, -20.0), 5);
+        assert_eq!(layout.hit_test(100.0, 5.0), 3);
+    }
+
+    #[test]
+    fn selections_split_at_bidi_runs() {
+        // "ab" followed by a right-to-left "אב", the selection 1..4 covers b and א.
+        let text = "abאב";
+        let layout = TextLayout::new(
+            text,
+            &[line(
+                0..6,
+                0.0,
+                vec![
+                    sequence(0.0, 0.0, &[(0, 10.0), (1, 10.0)], 0..2, false),
+                    sequence(20.0, 0.0, &[(4, 10.0), (2, 10.0)], 2..6, true),
+                ],
+            )],
+        );
+        let rects = layout.selection_rects(1..4);
+        assert_eq!(rects.iter().map(|r| (r.x_min, r.x_max)).collect::<Vec<_>>(), [(10.0, 20.0), (30.0, 40.0)]);
+        assert_eq!(layout.selected_text(1..4), Some("bא"));
+        assert_eq!(layout.selected_text(1..3), None);
+    }
+}
@@ -0,0 +1,219 @@
+use std::ops::Range;
+
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+
+use crate::font::FontSize;
+
+/// Horizontal alignment of the lines in a block, start and end follow the paragraph direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    End,
+    Center,
+    /// Stretches every line to the full width but the last of a paragraph and lines ending in a
+    /// forced break, extra space goes between words (and between characters with
+    /// [`Layout::with_inter_character`]).
+    Justify,
+}
+
+impl Align {
+    // Where the visible part of a line starts, relative to the left edge of the block.
+    pub(crate) fn offset(self, rtl: bool, free: f32) -> f32 {
+        match (self, rtl) {
+            (Align::Center, _) => free / 2.0,
+            (Align::Start | Align::Justify, false) | (Align::End, true) => 0.0,
+            (Align::Start | Align::Justify, true) | (Align::End, false) => free,
+        }
+    }
+}
+
+/// Vertical alignment of a block inside the box set by [`Layout::with_height`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// How a block of text is laid out. Lengths are in logical pixels like [`FontSize`], they are
+/// scaled by the window's scale factor together with it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Layout {
+    pub size: FontSize,
+    /// Width lines are wrapped at, `None` wraps at the right edge of the viewport of the
+    /// [`crate::Typesetter`]. Text drawn to a window has the window as its viewport.
+    pub max_width: Option<f32>,
+    /// Height of the box the block is aligned in. Without one the block's `y` is the first baseline,
+    /// with one it's the bottom of the box.
+    pub height: Option<f32>,
+    pub align: Align,
+    pub vertical_align: VerticalAlign,
+    /// Justified lines also get space between characters, not only between words.
+    pub inter_character: bool,
+    /// ISO 15924 code all text is shaped as, e.g. `*b"Arab"`. `None` itemizes the text by script.
+    pub script: Option<[u8; 4]>,
+    /// BCP 47 tag picking language specific forms, e.g. `"sr"` or `"tr"`. `None` lets harfbuzz guess.
+    pub language: Option<String>,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_size(mut self, size: FontSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+        self.vertical_align = vertical_align;
+        self
+    }
+
+    pub fn with_inter_character(mut self, inter_character: bool) -> Self {
+        self.inter_character = inter_character;
+        self
+    }
+
+    pub fn with_script(mut self, script: [u8; 4]) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    // The first baseline of a block `text_height` high placed at `y`, see `height`.
+    pub(crate) fn first_baseline(&self, y: f32, ascender: f32, text_height: f32, scale_factor: f32) -> f32 {
+        let Some(height) = self.height.map(|height| height * scale_factor) else {
+            return y;
+        };
+        // Screen y grows upwards, the box spans y..y + height.
+        let top = match self.vertical_align {
+            VerticalAlign::Top => y + height,
+            VerticalAlign::Middle => y + (height + text_height) / 2.0,
+            VerticalAlign::Bottom => y + text_height,
+        };
+        top - ascender
+    }
+}
+
+/// Splits `text[range]` into lines at UAX #14 break opportunities, taking as much text per line
+/// as fits into `max_width`. `width` measures a byte range of `text`, trailing whitespace isn't
+/// measured. Text without a break opportunity that is wider than `max_width` gets a line of its own.
+pub(crate) fn break_lines(text: &str, range: Range<usize>, max_width: f32, width: impl Fn(Range<usize>) -> f32) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = range.start;
+    // The furthest break that still fits on the current line.
+    let mut fit = None;
+    for (offset, opportunity) in linebreaks(&text[range.clone()]) {
+        let end = range.start + offset;
+        let visible = start + text[start..end].trim_end().len();
+        if width(start..visible) > max_width {
+            if let Some(fit) = fit.take() {
+                lines.push(start..fit);
+                start = fit;
+            }
+        }
+        fit = Some(end);
+        if opportunity == BreakOpportunity::Mandatory {
+            lines.push(start..end);
+            start = end;
+            fit = None;
+        }
+    }
+    if start < range.end || lines.is_empty() {
+        lines.push(start..range.end);
+    }
+    lines
+}
+
+/// Whether `text` ends in a mandatory line break.
+pub(crate) fn ends_in_break(text: &str) -> bool {
+    text.ends_with(['\n', '\r', '\u{b}', '\u{c}', '\u{85}', '\u{2028}', '\u{2029}'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every byte is one pixel wide.
+    fn lines(text: &str, range: Range<usize>, max_width: f32) -> Vec<&str> {
+        break_lines(text, range, max_width, |range| range.len() as f32)
+            .into_iter()
+            .map(|line| &text[line])
+            .collect()
+    }
+
+    #[test]
+    fn breaks_at_the_last_opportunity_that_fits() {
+        assert_eq!(lines("aaa bbb ccc", 0..11, 7.0), ["aaa bbb ", "ccc"]);
+        assert_eq!(lines("aaa bbb ccc", 0..11, 100.0), ["aaa bbb ccc"]);
+    }
+
+    #[test]
+    fn trailing_whitespace_does_not_count() {
+        assert_eq!(lines("aaa      bbb", 0..12, 3.0), ["aaa      ", "bbb"]);
+    }
+
+    #[test]
+    fn overlong_words_get_a_line_of_their_own() {
+        assert_eq!(lines("a bbbbbbbbbb c", 0..14, 5.0), ["a ", "bbbbbbbbbb ", "c"]);
+        assert_eq!(lines("bbbbbbbbbb", 0..10, 5.0), ["bbbbbbbbbb"]);
+    }
+
+    #[test]
+    fn mandatory_breaks_always_break() {
+        assert_eq!(lines("a\nb", 0..3, 100.0), ["a\n", "b"]);
+        assert_eq!(lines("a\u{2028}b\r\nc", 0..8, 100.0), ["a\u{2028}", "b\r\n", "c"]);
+    }
+
+    #[test]
+    fn empty_ranges_give_one_empty_line() {
+        assert_eq!(break_lines("", 0..0, 10.0, |range| range.len() as f32), vec![0..0]);
+        assert_eq!(break_lines("abc def", 3..3, 10.0, |range| range.len() as f32), vec![3..3]);
+    }
+
+    #[test]
+    fn start_and_end_follow_the_paragraph_direction() {
+        let offsets = |rtl| [Align::Start, Align::End, Align::Center, Align::Justify].map(|align| align.offset(rtl, 10.0));
+        assert_eq!(offsets(false), [0.0, 10.0, 5.0, 0.0]);
+        assert_eq!(offsets(true), [10.0, 0.0, 5.0, 10.0]);
+    }
+
+    #[test]
+    fn blocks_are_aligned_vertically_in_their_box() {
+        // A box 100 high at y = 0 holding text 30 high whose first line ascends 10.
+        let baseline = |vertical_align| Layout::new().with_height(100.0).with_vertical_align(vertical_align).first_baseline(0.0, 10.0, 30.0, 1.0);
+        assert_eq!(baseline(VerticalAlign::Top), 90.0);
+        assert_eq!(baseline(VerticalAlign::Middle), 55.0);
+        assert_eq!(baseline(VerticalAlign::Bottom), 20.0);
+        assert_eq!(Layout::new().with_height(100.0).first_baseline(5.0, 10.0, 30.0, 2.0), 195.0);
+        assert_eq!(Layout::new().first_baseline(5.0, 10.0, 30.0, 1.0), 5.0);
+    }
+
+    #[test]
+    fn ranges_are_offsets_into_the_whole_text() {
+        assert_eq!(break_lines("xx aaa bbb", 3..10, 3.0, |range| range.len() as f32), [3..7, 7..10]);
+    }
+}
@@ -3,7 +3,14 @@ use winit::window::Window;
 use self::text::TextState;
 
 pub(crate) mod atlas;
+pub(crate) mod attributed;
+pub(crate) mod color;
+pub(crate) mod hit;
+pub(crate) mod layout;
+pub(crate) mod script;
+pub(crate) mod shaper;
 pub(crate) mod text;
+pub(crate) mod typeset;
 
 const BACKENDS: Option<wgpu::Backends> = wgpu::Backends::from_bits(
     wgpu::Backends::VULKAN.bits() | wgpu::Backends::GL.bits() | wgpu::Backends::METAL.bits(),
@@ -114,6 +121,9 @@ impl State {
             });
         let textures = text_state.create_texture_binds(&self.device, &self.queue);
         let buffers = text_state.create_buffers(&self.device, self.size.width, self.size.height);
+        let color_textures = text_state.create_color_texture_binds(&self.device, &self.queue);
+        let color_buffers = text_state.create_color_buffers(&self.device, self.size.width, self.size.height);
+        let selection_buffer = text_state.create_selection_buffer(&self.device, self.size.width, self.size.height);
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
@@ -133,6 +143,13 @@ impl State {
                 depth_stencil_attachment: None,
             });
 
+            if let Some((vertex_buffer, index_buffer, num_indices)) = &selection_buffer {
+                render_pass.set_pipeline(&text_state.selection_pipeline);
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.draw_indexed(0..*num_indices, 0, 0..1);
+            }
+
             render_pass.set_pipeline(&text_state.pipeline);
             for font in textures.keys().into_iter() {
                 render_pass.set_bind_group(0, textures.get(font).unwrap(), &[]);
@@ -141,6 +158,15 @@ impl State {
                 render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
                 render_pass.draw_indexed(0..*num_indices, 0, 0..1);
             }
+
+            render_pass.set_pipeline(&text_state.color_pipeline);
+            for (font, texture) in color_textures.iter() {
+                render_pass.set_bind_group(0, texture, &[]);
+                let (vertex_buffer, index_buffer, num_indices) = color_buffers.get(font).unwrap();
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.draw_indexed(0..*num_indices, 0, 0..1);
+            }
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -186,6 +212,10 @@ pub struct UVRect {
 struct Vertex {
     position: [f32; 2],
     uv: [f32; 2],
+    // Lowers the signed distance threshold, used for synthetic bold.
+    embolden: f32,
+    // Straight RGBA the outline is filled with.
+    color: [f32; 4],
 }
 
 impl Vertex {
@@ -204,6 +234,16 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -218,6 +258,10 @@ struct Quad {
     y2: f32,
     uv: [f32; 2],
     xy: [f32; 2],
+    // Horizontal shift of the bottom and top edge.
+    skew: [f32; 2],
+    embolden: f32,
+    color: [f32; 4],
 }
 
 impl Quad {
@@ -238,26 +282,56 @@ impl Quad {
             y2: (2.0 * (y + h) / sh) - 1.0,
             uv,
             xy,
+            skew: [0.0, 0.0],
+            embolden: 0.0,
+            color: [0.0, 0.0, 0.0, 1.0],
         }
     }
 
+    /// Shears the quad around the `baseline` by `skew` pixels per pixel of height, for synthetic oblique.
+    pub fn skewed(mut self, skew: f32, baseline: f32, sw: f32, sh: f32) -> Self {
+        let shift = |y: f32| ((y + 1.0) * sh / 2.0 - baseline) * skew * 2.0 / sw;
+        self.skew = [shift(self.y1), shift(self.y2)];
+        self
+    }
+
+    /// Lowers the distance threshold by `embolden` in distance field units, for synthetic bold.
+    pub fn emboldened(mut self, embolden: f32) -> Self {
+        self.embolden = embolden;
+        self
+    }
+
+    /// Fills the glyph with `color`, straight RGBA. Color glyphs ignore it.
+    pub fn colored(mut self, color: [u8; 4]) -> Self {
+        self.color = color.map(|c| c as f32 / 255.0);
+        self
+    }
+
     pub fn vertices(&self) -> [Vertex; 4] {
         [
             Vertex {
-                position: [self.x1, self.y1],
+                position: [self.x1 + self.skew[0], self.y1],
                 uv: [self.uv[0], self.xy[1]],
+                embolden: self.embolden,
+                color: self.color,
             },
             Vertex {
-                position: [self.x2, self.y1],
+                position: [self.x2 + self.skew[0], self.y1],
                 uv: [self.xy[0], self.xy[1]],
+                embolden: self.embolden,
+                color: self.color,
             },
             Vertex {
-                position: [self.x2, self.y2],
+                position: [self.x2 + self.skew[1], self.y2],
                 uv: [self.xy[0], self.uv[1]],
+                embolden: self.embolden,
+                color: self.color,
             },
             Vertex {
-                position: [self.x1, self.y2],
+                position: [self.x1 + self.skew[1], self.y2],
                 uv: [self.uv[0], self.uv[1]],
+                embolden: self.embolden,
+                color: self.color,
             },
         ]
     }
@@ -0,0 +1,71 @@
+use std::ops::Range;
+
+use unicode_script::{Script, UnicodeScript};
+
+/// Splits `text[range]` into runs of a single script. Common and inherited characters (spaces,
+/// punctuation, digits, combining marks) join the run before them, at the start the first run.
+pub(crate) fn itemize(text: &str, range: Range<usize>) -> Vec<(Range<usize>, [u8; 4])> {
+    let mut items: Vec<(Range<usize>, Script)> = Vec::new();
+    for (offset, c) in text[range.clone()].char_indices() {
+        let start = range.start + offset;
+        let end = start + c.len_utf8();
+        let script = c.script();
+        match items.last_mut() {
+            Some((item, current)) if script == *current || is_neutral(script) => item.end = end,
+            Some((item, current)) if is_neutral(*current) => {
+                *current = script;
+                item.end = end;
+            }
+            _ => items.push((start..end, script)),
+        }
+    }
+    items.into_iter().map(|(item, script)| (item, tag(script))).collect()
+}
+
+fn is_neutral(script: Script) -> bool {
+    matches!(script, Script::Common | Script::Inherited | Script::Unknown)
+}
+
+// The ISO 15924 code harfbuzz takes, e.g. `Latn` or `Arab`.
+fn tag(script: Script) -> [u8; 4] {
+    script.short_name().as_bytes().try_into().unwrap_or(*b"Zyyy")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(text: &str) -> Vec<(&str, [u8; 4])> {
+        itemize(text, 0..text.len())
+            .into_iter()
+            .map(|(range, script)| (&text[range], script))
+            .collect()
+    }
+
+    #[test]
+    fn neutrals_join_the_run_before_them() {
+        // Spaces, punctuation, digits and a combining acute accent.
+        assert_eq!(items("abc, 123 e\u{301}!"), [("abc, 123 e\u{301}!", *b"Latn")]);
+    }
+
+    #[test]
+    fn leading_neutrals_join_the_first_run() {
+        assert_eq!(items("(12) abc"), [("(12) abc", *b"Latn")]);
+        // Without any run to join neutrals stay common.
+        assert_eq!(items("12 "), [("12 ", *b"Zyyy")]);
+    }
+
+    #[test]
+    fn mixed_scripts_split_into_items() {
+        assert_eq!(
+            items("Hi مرحبا नमस्ते!"),
+            [("Hi ", *b"Latn"), ("مرحبا ", *b"Arab"), ("नमस्ते!", *b"Deva")]
+        );
+    }
+
+    #[test]
+    fn items_are_offsets_into_the_whole_text() {
+        let text = "abc مرحبا";
+        assert_eq!(itemize(text, 2..text.len()), [(2..4, *b"Latn"), (4..text.len(), *b"Arab")]);
+    }
+}
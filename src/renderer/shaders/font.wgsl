@@ -3,11 +3,15 @@
 struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) tex_coords: vec2<f32>,
+    @location(2) embolden: f32,
+    @location(3) color: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) embolden: f32,
+    @location(2) color: vec4<f32>,
 }
 
 @vertex
@@ -16,6 +20,8 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
     out.tex_coords = model.tex_coords;
+    out.embolden = model.embolden;
+    out.color = model.color;
     out.clip_position = vec4<f32>(model.position.xy, 0.0, 1.0);
     return out;
 }
@@ -29,13 +35,27 @@ var msdf_sampler: sampler;
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     var msd = textureSample(msdf, msdf_sampler, in.tex_coords);
     var sd = median(msd.x, msd.y, msd.z);
-    var screen_px_distance = screenPxRange(in.tex_coords)*(sd - 0.5);
+    // Synthetic bold moves the edge outwards by lowering the threshold.
+    var screen_px_distance = screenPxRange(in.tex_coords)*(sd - 0.5 + in.embolden);
     var opacity = clamp(screen_px_distance + 0.5, 0.0, 1.0);
-    return mix(vec4<f32>(0.0, 1.0, 0.4, 0.0), vec4<f32>(0.0, 0.0, 0.0, 1.0), opacity);
+    return mix(vec4<f32>(0.0, 1.0, 0.4, 0.0), in.color, opacity);
     //return vec4<f32>(msd.xyz, 1.0);
     //return vec4<f32>(opacity, opacity, opacity, 1.0);
 }
 
+// Color glyphs (emoji) are plain RGBA, they are sampled as is. COLR layers in the text color
+// are baked with the color of their run.
+@fragment
+fn fs_color(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(msdf, msdf_sampler, in.tex_coords);
+}
+
+// Selection highlights are untextured quads.
+@fragment
+fn fs_solid(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
+
 fn median(r: f32, g: f32, b: f32) -> f32 {
     return max(min(r, g), min(max(r, g), b));
 }
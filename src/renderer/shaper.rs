@@ -0,0 +1,129 @@
+use std::ops::Range;
+
+use harfbuzz::sys;
+use ttf_parser::GlyphId;
+
+use crate::font::{self, FontData};
+
+/// A shaped glyph, positions are in font units.
+#[derive(Debug)]
+pub(crate) struct Glyph {
+    pub glyph_id: GlyphId,
+    /// Byte offset of the first character the glyph was shaped from.
+    pub cluster: usize,
+    pub x_advance: f32,
+    pub y_advance: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+}
+
+/// Owns the harfbuzz font of a [`font::Font`], created once and reused for every run shaped with it.
+pub(crate) struct Shaper {
+    font: *mut sys::hb_font_t,
+    units_per_em: u16,
+    // Synthetic bold widens every spacing glyph, in font units.
+    extra_advance: f32,
+    // The face borrows these bytes, they have to outlive `font`.
+    _data: FontData,
+}
+
+impl Shaper {
+    pub fn new(font: &font::Font) -> Self {
+        let data = font.data.clone();
+        let units_per_em = font.face().map(|face| face.units_per_em()).unwrap_or(1000);
+        let variations = font
+            .variations
+            .iter()
+            .map(|v| sys::hb_variation_t {
+                tag: u32::from_be_bytes(v.axis),
+                value: v.value,
+            })
+            .collect::<Vec<_>>();
+        let blob = harfbuzz::Blob::new_read_only(&data);
+        let hb_font = unsafe {
+            // The font keeps its own reference to the face (and the face to the blob).
+            let face = sys::hb_face_create(blob.as_raw(), font.index);
+            let hb_font = sys::hb_font_create(face);
+            sys::hb_face_destroy(face);
+            // Positions come back in font units, the caller scales them.
+            sys::hb_font_set_scale(hb_font, units_per_em as i32, units_per_em as i32);
+            sys::hb_font_set_variations(hb_font, variations.as_ptr(), variations.len() as u32);
+            hb_font
+        };
+        drop(blob);
+        Self {
+            font: hb_font,
+            units_per_em,
+            extra_advance: font.synthesis.advance(units_per_em),
+            _data: data,
+        }
+    }
+
+    pub fn units_per_em(&self) -> u16 {
+        self.units_per_em
+    }
+
+    /// Shapes `buffer` for text rendered at `pixels_per_em`, which picks size specific
+    /// tracking and bitmap strikes.
+    pub fn shape(&self, buffer: harfbuzz::Buffer, features: &[sys::hb_feature_t], pixels_per_em: f32) -> Vec<Glyph> {
+        unsafe {
+            let ppem = pixels_per_em.round().max(1.0) as u32;
+            sys::hb_font_set_ppem(self.font, ppem, ppem);
+            sys::hb_shape(self.font, buffer.as_ptr(), features.as_ptr(), features.len() as u32);
+
+            let mut glyph_count: u32 = 0;
+            let glyph_info = sys::hb_buffer_get_glyph_infos(buffer.as_ptr(), &mut glyph_count);
+            let glyph_pos = sys::hb_buffer_get_glyph_positions(buffer.as_ptr(), &mut glyph_count);
+            if glyph_count == 0 {
+                return Vec::new();
+            }
+            let glyph_info = std::slice::from_raw_parts(glyph_info, glyph_count as usize);
+            let glyph_pos = std::slice::from_raw_parts(glyph_pos, glyph_count as usize);
+            glyph_info
+                .iter()
+                .zip(glyph_pos)
+                .map(|(info, pos)| Glyph {
+                    glyph_id: GlyphId(info.codepoint as u16),
+                    cluster: info.cluster as usize,
+                    // Marks keep their zero advance.
+                    x_advance: match pos.x_advance {
+                        0 => 0.0,
+                        advance => advance as f32 + self.extra_advance,
+                    },
+                    y_advance: pos.y_advance as f32,
+                    x_offset: pos.x_offset as f32,
+                    y_offset: pos.y_offset as f32,
+                })
+                .collect()
+        }
+    }
+}
+
+/// A buffer holding `text[range]`, with the rest of `text` as context so joining and
+/// contextual forms carry across runs. Clusters are byte offsets into `text`. `script` is an
+/// ISO 15924 code and `language` a BCP 47 tag, harfbuzz guesses the language if there is none.
+pub(crate) fn buffer(text: &str, range: Range<usize>, direction: harfbuzz::Direction, script: [u8; 4], language: Option<&str>) -> harfbuzz::Buffer {
+    let mut buffer = harfbuzz::Buffer::new();
+    unsafe {
+        sys::hb_buffer_add_utf8(
+            buffer.as_ptr(),
+            text.as_ptr() as *const std::os::raw::c_char,
+            text.len() as i32,
+            range.start as u32,
+            range.len() as i32,
+        );
+        buffer.set_script(sys::hb_script_from_iso15924_tag(u32::from_be_bytes(script)));
+    }
+    buffer.set_direction(direction);
+    if let Some(language) = language {
+        buffer.set_language(harfbuzz::Language::from_string(language));
+    }
+    buffer.guess_segment_properties();
+    buffer
+}
+
+impl Drop for Shaper {
+    fn drop(&mut self) {
+        unsafe { sys::hb_font_destroy(self.font) }
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     collections::{HashMap, HashSet},
+    ops::Range,
     sync::Arc,
 };
 
@@ -7,36 +8,44 @@ use image::EncodableLayout;
 use ttf_parser::GlyphId;
 use wgpu::util::DeviceExt;
 
-use crate::font::{self, Font};
+use crate::font::{self, color, Font, FontId};
 
-use super::{atlas::FontAtlas, Vertex, Quad};
-
-#[derive(Debug)]
-pub(crate) struct Glyph {
-    glyph_id: GlyphId,
-    x_advance: f32,
-    y_advance: f32,
-    x_offset: f32,
-    y_offset: f32,
-}
-
-#[derive(Debug)]
-pub(crate) struct GlyphSequence {
-    glyphs: Vec<Glyph>,
-    x: u32,
-    y: u32,
-    font: Arc<font::Font>,
-}
+use super::{
+    atlas::FontAtlas,
+    attributed::AttributedText,
+    color::ColorAtlas,
+    layout::Layout,
+    typeset::{GlyphSequence, TextBlock, TextLayout, Typesetter},
+    Vertex, Quad,
+};
 
 // TODO: Make a global atlas where all font-glyphs are stored in, so that it can be rendered in one drawcall
 pub(crate) struct TextState {
-    font_to_glyph_ids: HashMap<Arc<Font>, HashSet<GlyphId>>,
-    font_to_sequences: HashMap<Arc<Font>, Vec<GlyphSequence>>,
-    font_to_atlas: HashMap<Arc<Font>, FontAtlas>,
+    // Keyed by id so lookups don't hash the font bytes.
+    fonts: HashMap<FontId, Arc<Font>>,
+    typesetter: Typesetter,
+    font_to_glyph_ids: HashMap<FontId, HashSet<GlyphId>>,
+    // Text colors by font, COLR layers in the text color are baked in each of them.
+    font_to_foregrounds: HashMap<FontId, HashSet<[u8; 4]>>,
+    font_to_sequences: HashMap<FontId, Vec<GlyphSequence>>,
+    font_to_atlas: HashMap<FontId, FontAtlas>,
+    font_to_color_atlas: HashMap<FontId, ColorAtlas>,
+    blocks: Vec<TextBlock>,
+    layouts: Vec<TextLayout>,
+    // Ordered byte ranges by block.
+    selections: HashMap<usize, Range<usize>>,
+    selection_color: [u8; 4],
     pub pipeline: wgpu::RenderPipeline,
+    /// Draws color glyphs straight from their RGBA atlas, after the MSDF pass.
+    pub color_pipeline: wgpu::RenderPipeline,
+    /// Draws selection highlights, before the glyphs.
+    pub selection_pipeline: wgpu::RenderPipeline,
 }
 
 impl TextState {
+    // Default selection highlight.
+    const SELECTION: [u8; 4] = [179, 215, 255, 255];
+
     pub fn new(state: &super::State) -> Self {
         // create the pipeline layout
         let shader = state
@@ -82,158 +91,239 @@ impl TextState {
                     push_constant_ranges: &[],
                 });
 
-        let pipeline = state
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Text Pipeline"),
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: "vs_main",
-                    buffers: &[Vertex::desc()],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: "fs_main",
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: state.config.format,
-                        blend: Some(wgpu::BlendState::REPLACE),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: 4,
-                    mask: !0,
-                    alpha_to_coverage_enabled: true,
-                },
-                multiview: None,
-            });
+        let selection_pipeline_layout =
+            state
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Selection Pipeline Layout"),
+                    bind_group_layouts: &[],
+                    push_constant_ranges: &[],
+                });
+
+        let create_pipeline = |label, layout, fragment_entry_point| {
+            state
+                .device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: "vs_main",
+                        buffers: &[Vertex::desc()],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: fragment_entry_point,
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: state.config.format,
+                            blend: Some(wgpu::BlendState::REPLACE),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState {
+                        count: 4,
+                        mask: !0,
+                        alpha_to_coverage_enabled: true,
+                    },
+                    multiview: None,
+                })
+        };
+        let pipeline = create_pipeline("Text Pipeline", &pipeline_layout, "fs_main");
+        let color_pipeline = create_pipeline("Color Text Pipeline", &pipeline_layout, "fs_color");
+        let selection_pipeline = create_pipeline("Selection Pipeline", &selection_pipeline_layout, "fs_solid");
+
+        let mut typesetter = Typesetter::new(state.window().scale_factor());
+        typesetter.set_viewport_width(Some(state.size.width as f32));
 
         Self {
+            fonts: HashMap::new(),
+            typesetter,
             font_to_glyph_ids: HashMap::new(),
+            font_to_foregrounds: HashMap::new(),
             font_to_sequences: HashMap::new(),
             font_to_atlas: HashMap::new(),
+            font_to_color_atlas: HashMap::new(),
+            blocks: Vec::new(),
+            layouts: Vec::new(),
+            selections: HashMap::new(),
+            selection_color: Self::SELECTION,
             pipeline,
+            color_pipeline,
+            selection_pipeline,
         }
     }
 
-    //TODO: Cache
-    pub fn draw(&mut self, x: u32, y: u32, text: &str, font: Arc<font::Font>) {
-        let blob = harfbuzz::Blob::new_read_only(font.data);
-        let mut buffer = harfbuzz::Buffer::with(text);
+    pub fn draw(&mut self, x: u32, y: u32, text: &str, font: Arc<font::Font>, features: &font::FontFeatures, layout: &Layout) -> usize {
+        self.draw_with_fallbacks(x, y, text, &[font], features, layout)
+    }
 
-        buffer.guess_segment_properties();
+    /// Draws `text` with the first font in `fonts`, characters it lacks are shaped with the
+    /// next font in the chain that has them. Each segment ends up in the atlas of its font.
+    /// Paragraphs are wrapped to the width of `layout` and mixed direction text is reordered per
+    /// the Unicode bidi algorithm. Ranges in `features` are byte ranges of `text`.
+    pub fn draw_with_fallbacks(&mut self, x: u32, y: u32, text: &str, fonts: &[Arc<font::Font>], features: &font::FontFeatures, layout: &Layout) -> usize {
+        self.draw_attributed(x, y, &AttributedText::new(text), fonts, features, layout)
+    }
 
-        let glyph_sequence = GlyphSequence {
-            glyphs: Self::shape(blob, buffer, Some(&[Self::KERN_FEATURE])),
-            x,
-            y,
-            font: font.clone(),
+    /// Draws rich text as one block, the attributes of its spans override `fonts`, the size of
+    /// `layout` and the color, and add to `features`. `x` and `y` are logical pixels like the
+    /// lengths of `layout`. Returns the index of the block for [`TextState::text_layout`].
+    pub fn draw_attributed(&mut self, x: u32, y: u32, text: &AttributedText, fonts: &[Arc<font::Font>], features: &font::FontFeatures, layout: &Layout) -> usize {
+        let block = TextBlock {
+            x: x as f32,
+            y: y as f32,
+            text: text.clone(),
+            fonts: fonts.to_vec(),
+            features: features.clone(),
+            layout: layout.clone(),
         };
-        let glyph_ids = {
-            if let Some(set) = self.font_to_glyph_ids.get_mut(&font) {
-                set
-            } else {
-                self.font_to_glyph_ids.insert(font.clone(), HashSet::new());
-                self.font_to_glyph_ids.get_mut(&font).unwrap()
-            }
-        };
-        let glyph_ids_new = glyph_sequence.glyphs.iter().map(|g| g.glyph_id).collect::<Vec<_>>();
-        glyph_ids_new.iter().for_each(|c| {glyph_ids.insert(*c);});
-        let atlas = FontAtlas::new(glyph_ids.iter().map(|v|*v).collect::<Vec<_>>(), font.clone()).unwrap();
-        atlas.texture.save_with_format("./atlas.png", image::ImageFormat::Png);
-        let sequences = {
-            if let Some(set) = self.font_to_sequences.get_mut(&font) {
-                set
-            } else {
-                self.font_to_sequences.insert(font.clone(), Vec::new());
-                self.font_to_sequences.get_mut(&font).unwrap()
+        let mut stale = HashMap::new();
+        self.lay_out(&block, &mut stale);
+        self.update_atlases(stale);
+        self.blocks.push(block);
+        self.blocks.len() - 1
+    }
+
+    /// The current layout of a drawn block, in physical screen pixels, for hit testing.
+    pub fn text_layout(&self, block: usize) -> Option<&TextLayout> {
+        self.layouts.get(block)
+    }
+
+    /// Highlights `selection`, a byte range of the block's text, behind its glyphs. The range
+    /// may be reversed, e.g. from a drag to the left. An empty range clears the selection.
+    pub fn select(&mut self, block: usize, selection: Range<usize>) {
+        let selection = selection.start.min(selection.end)..selection.start.max(selection.end);
+        if selection.is_empty() {
+            self.selections.remove(&block);
+        } else {
+            self.selections.insert(block, selection);
+        }
+    }
+
+    /// Straight RGBA of the selection highlights, drawn opaque.
+    pub fn set_selection_color(&mut self, color: [u8; 4]) {
+        self.selection_color = color;
+    }
+
+    pub fn selected_text(&self, block: usize) -> Option<&str> {
+        let selection = self.selections.get(&block)?;
+        self.layouts.get(block)?.selected_text(selection.clone())
+    }
+
+    /// Wraps every block again for the new window width.
+    pub fn resize(&mut self, screen_width: u32) {
+        if Some(screen_width as f32) != self.typesetter.viewport_width() {
+            self.typesetter.set_viewport_width(Some(screen_width as f32));
+            self.lay_out_again();
+        }
+    }
+
+    /// Sizes every block again for a window moved to a display with a different scale factor.
+    pub fn rescale(&mut self, scale_factor: f64) {
+        if scale_factor != self.typesetter.scale_factor() {
+            self.typesetter.set_scale_factor(scale_factor);
+            self.lay_out_again();
+        }
+    }
+
+    fn lay_out_again(&mut self) {
+        self.font_to_sequences.values_mut().for_each(Vec::clear);
+        self.layouts.clear();
+        let blocks = std::mem::take(&mut self.blocks);
+        let mut stale = HashMap::new();
+        for block in &blocks {
+            self.lay_out(block, &mut stale);
+        }
+        self.blocks = blocks;
+        self.update_atlases(stale);
+    }
+
+    // Fonts whose atlases need to be built again are added to `stale`, with whether they got new
+    // glyphs or only new text colors. Building an atlas is slow, callers do it once per draw.
+    fn lay_out(&mut self, block: &TextBlock, stale: &mut HashMap<FontId, bool>) {
+        let lines = self.typesetter.lay_out(block);
+        self.layouts.push(TextLayout::new(block.text.text(), &lines));
+        for line in lines {
+            for sequence in line.sequences {
+                self.push_sequence(sequence, stale);
             }
-        };
-        sequences.push(glyph_sequence);
+        }
+    }
 
-        self.font_to_atlas.insert(font.clone(), atlas);
+    fn push_sequence(&mut self, glyph_sequence: GlyphSequence, stale: &mut HashMap<FontId, bool>) {
+        let font = glyph_sequence.font.clone();
+        let id = font.id();
+        self.fonts.entry(id).or_insert_with(|| font.clone());
+        let glyph_ids = self.font_to_glyph_ids.entry(id).or_default();
+        let added = glyph_sequence
+            .glyphs
+            .iter()
+            .fold(false, |added, glyph| glyph_ids.insert(glyph.glyph_id) || added);
+        let recolored = self.font_to_foregrounds.entry(id).or_default().insert(glyph_sequence.color);
+        self.font_to_sequences.entry(id).or_default().push(glyph_sequence);
+        // Laying text out again (e.g. on resize) mostly reuses glyphs that are already in the atlases.
+        if added || recolored {
+            *stale.entry(id).or_default() |= added;
+        }
     }
 
-    const KERN_FEATURE: harfbuzz::sys::hb_feature_t = harfbuzz::sys::hb_feature_t {
-        tag: 0x6b65726e,
-        value: 1,
-        start: 0,
-        end: u32::MAX,
-    };
-    const LIGA_FEATURE: harfbuzz::sys::hb_feature_t = harfbuzz::sys::hb_feature_t {
-        tag: 0x6c696761,
-        value: 1,
-        start: 0,
-        end: u32::MAX,
-    };
-    const CLIG_FEATURE: harfbuzz::sys::hb_feature_t = harfbuzz::sys::hb_feature_t {
-        tag: 0x636c6967,
-        value: 1,
-        start: 0,
-        end: u32::MAX,
-    };
-
-    fn shape(blob: harfbuzz::Blob, buffer: harfbuzz::Buffer, features: Option<&[harfbuzz::sys::hb_feature_t]>) -> Vec<Glyph> {
-        unsafe {
-            let font = harfbuzz::sys::hb_font_create(harfbuzz::sys::hb_face_create(blob.as_raw(), 0));
-            if let Some(features) = features {
-                harfbuzz::sys::hb_shape(
-                    font,
-                    buffer.as_ptr(),
-                    features.as_ptr(),
-                    features.len() as u32,
-                );
-            } else {
-                harfbuzz::sys::hb_shape(
-                    font,
-                    buffer.as_ptr(),
-                    std::ptr::null(),
-                    0,
-                );
+    fn update_atlases(&mut self, stale: HashMap<FontId, bool>) {
+        for (id, added) in stale {
+            let font = self.fonts[&id].clone();
+            // Color glyphs (emoji) go into their own RGBA atlas, glyphs with an outline are rendered as MSDF.
+            // Anything else (blanks, bitmap only fonts without a color glyph) is not drawn.
+            let Ok(face) = font.face() else {
+                continue;
+            };
+            let (color_ids, outline_ids): (Vec<GlyphId>, Vec<GlyphId>) = self.font_to_glyph_ids[&id]
+                .iter()
+                .filter(|id| color::is_color_glyph(&face, **id) || FontAtlas::has_outline(&face, **id))
+                .partition(|id| color::is_color_glyph(&face, **id));
+            // A new text color only changes the color atlas.
+            if added {
+                let atlas = (!outline_ids.is_empty())
+                    .then(|| FontAtlas::new(outline_ids, font.clone()).ok())
+                    .flatten()
+                    .filter(|atlas| !atlas.map.is_empty());
+                match atlas {
+                    Some(atlas) => self.font_to_atlas.insert(id, atlas),
+                    None => self.font_to_atlas.remove(&id),
+                };
+            }
+            match ColorAtlas::new(color_ids, &self.font_to_foregrounds[&id], font.clone()) {
+                Ok(color_atlas) if !color_atlas.map.is_empty() => {
+                    self.font_to_color_atlas.insert(id, color_atlas);
+                }
+                _ => {
+                    self.font_to_color_atlas.remove(&id);
+                }
             }
-            let mut glyph_count: u32 = 0;
-            let glyph_info = harfbuzz::sys::hb_buffer_get_glyph_infos(
-                buffer.as_ptr(),
-                (&mut glyph_count) as *mut u32,
-            );
-            let glyph_pos = harfbuzz::sys::hb_buffer_get_glyph_positions(
-                buffer.as_ptr(),
-                (&mut glyph_count) as *mut u32,
-            );
-            let glyph_info = &*std::ptr::slice_from_raw_parts_mut(glyph_info, glyph_count as usize);
-            let glyph_pos = &*std::ptr::slice_from_raw_parts_mut(glyph_pos, glyph_count as usize);
-            (0..glyph_count)
-                .map(|i| Glyph {
-                    glyph_id: GlyphId(glyph_info[i as usize].codepoint as u16),
-                    x_advance: glyph_pos[i as usize].x_advance as f32,
-                    y_advance: glyph_pos[i as usize].y_advance as f32,
-                    x_offset: glyph_pos[i as usize].x_offset as f32,
-                    y_offset: glyph_pos[i as usize].y_offset as f32,
-                })
-                .collect()
         }
     }
 
     // TODO: Cache
-    pub fn create_texture_binds(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> HashMap<Arc<Font>, wgpu::BindGroup> {
-        self.font_to_atlas.iter().map(|(font, atlas)| (font.clone(), Self::create_texture_bind(atlas, device, queue))).collect()
+    pub fn create_texture_binds(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> HashMap<FontId, wgpu::BindGroup> {
+        self.font_to_atlas.iter().map(|(id, atlas)| (*id, Self::create_texture_bind(&atlas.texture, device, queue))).collect()
+    }
+
+    pub fn create_color_texture_binds(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> HashMap<FontId, wgpu::BindGroup> {
+        self.font_to_color_atlas.iter().map(|(id, atlas)| (*id, Self::create_texture_bind(&atlas.texture, device, queue))).collect()
     }
 
-    fn create_texture_bind(atlas: &FontAtlas, device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::BindGroup {
+    fn create_texture_bind(image: &image::RgbaImage, device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::BindGroup {
         let texture_size = wgpu::Extent3d {
-            width: atlas.texture.width(),
-            height: atlas.texture.height(),
+            width: image.width(),
+            height: image.height(),
             depth_or_array_layers: 1,
         };
         let texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -253,11 +343,11 @@ impl TextState {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            atlas.texture.as_bytes(),
+            image.as_bytes(),
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * atlas.texture.width()), // 4 channels * 4 bytes per channel * number of pixels
-                rows_per_image: Some(atlas.texture.height()),
+                bytes_per_row: Some(4 * image.width()), // 4 channels * 4 bytes per channel * number of pixels
+                rows_per_image: Some(image.height()),
             },
             texture_size,
         );
@@ -315,41 +405,50 @@ impl TextState {
     }
 
     // TODO: Cache
-    pub fn create_buffers(&self, device: &wgpu::Device, screen_width: u32, screen_height: u32) -> HashMap<Arc<Font>, (wgpu::Buffer, wgpu::Buffer, u32)> {
-        self.font_to_atlas.iter().map(|(font, atlas)| {
-            let glyph_sequences = self.font_to_sequences.get(font).unwrap();
-            (font.clone(), Self::create_buffer(font, atlas, glyph_sequences, device, screen_width, screen_height))
+    pub fn create_buffers(&self, device: &wgpu::Device, screen_width: u32, screen_height: u32) -> HashMap<FontId, (wgpu::Buffer, wgpu::Buffer, u32)> {
+        self.font_to_atlas.iter().map(|(id, atlas)| {
+            let glyph_sequences = self.font_to_sequences.get(id).unwrap();
+            (*id, Self::create_buffer(&self.fonts[id], atlas, glyph_sequences, device, screen_width, screen_height))
         }).collect()
     }
 
-    fn create_buffer(font: &Arc<Font>, atlas: &FontAtlas, glyph_sequences: &Vec<GlyphSequence>, device: &wgpu::Device, screen_width: u32, screen_height: u32) -> (wgpu::Buffer, wgpu::Buffer, u32) {
+    fn create_buffer(font: &Font, atlas: &FontAtlas, glyph_sequences: &Vec<GlyphSequence>, device: &wgpu::Device, screen_width: u32, screen_height: u32) -> (wgpu::Buffer, wgpu::Buffer, u32) {
         let mut vertices: Vec<Vertex> = Vec::new();
         let mut indices: Vec<u16> = Vec::new();
 
-        let scale = 0.015;
-
-        if let Ok(face) = ttf_parser::Face::parse(font.data, 0) {
+        if let Ok(face) = font.face() {
 
-            let whitespace = face.glyph_index(' ').unwrap();
+            let whitespace = face.glyph_index(' ');
+            let padding = FontAtlas::PADDING as f32;
+            // Synthetic bold grows the outline into the atlas padding, shifted right to keep the left bearing.
+            let embolden = match font.synthesis.bold {
+                true => (font::Synthesis::EMBOLDEN * face.units_per_em() as f32).min(padding),
+                false => 0.0,
+            };
 
             for sequence in glyph_sequences {
-                let mut cursor = (sequence.x as f32, sequence.y as f32);
+                let scale = sequence.scale;
+                let mut cursor = (sequence.x, sequence.y);
                 for glyph in &sequence.glyphs {
-                    if glyph.glyph_id != whitespace {
-                        let bound = face.glyph_bounding_box(glyph.glyph_id).unwrap_or(face.glyph_bounding_box(GlyphId::default()).unwrap());
-                        let bearing_x = face.glyph_hor_side_bearing(glyph.glyph_id).unwrap_or(face.glyph_hor_side_bearing(GlyphId::default()).unwrap_or(0)) as f32;
-                        let bearing_y = face.glyph_ver_side_bearing(glyph.glyph_id).unwrap_or(face.glyph_ver_side_bearing(GlyphId::default()).unwrap_or(0)) as f32;
-                        let uv = atlas.map.get(&glyph.glyph_id).unwrap();
+                    // Color glyphs have no MSDF, they are drawn from the color atlas. Glyphs without an
+                    // outline are not in either atlas and only advance the cursor.
+                    let uv = atlas.map.get(&glyph.glyph_id).filter(|_| Some(glyph.glyph_id) != whitespace);
+                    // The atlas image spans the bounds of the instanced outline plus padding, the
+                    // side bearings of `hmtx` aren't varied for every variable font.
+                    if let (Some(uv), Some(bound)) = (uv, face.glyph_bounding_box(glyph.glyph_id)) {
                         let quad = Quad::new(
-                            cursor.0 + (glyph.x_offset + bearing_x) * scale,
-                            cursor.1 + (glyph.y_offset + bearing_y + bound.y_min as f32) * scale,
-                            (bound.width()) as f32 * scale,
-                            (bound.height()) as f32 * scale,
+                            cursor.0 + (glyph.x_offset + bound.x_min as f32 + embolden - padding) * scale,
+                            cursor.1 + (glyph.y_offset + bound.y_min as f32 - padding) * scale,
+                            (bound.width() as f32 + 2.0 * padding) * scale,
+                            (bound.height() as f32 + 2.0 * padding) * scale,
                             [uv.u, uv.v],
                             [uv.u + uv.w, uv.v + uv.h],
                             screen_width as f32,
                             screen_height as f32,
-                        );
+                        )
+                        .emboldened(embolden / FontAtlas::RANGE as f32)
+                        .colored(sequence.color);
+                        let quad = Self::synthesize_oblique(font, quad, cursor.1 + glyph.y_offset * scale, screen_width, screen_height);
                         indices.append(&mut quad.indices((vertices.len()) as u16).to_vec());
                         vertices.append(&mut quad.vertices().to_vec());
                     }
@@ -359,14 +458,90 @@ impl TextState {
             }
         }
 
+        Self::upload(device, &vertices, &indices)
+    }
+
+    pub fn create_color_buffers(&self, device: &wgpu::Device, screen_width: u32, screen_height: u32) -> HashMap<FontId, (wgpu::Buffer, wgpu::Buffer, u32)> {
+        self.font_to_color_atlas.iter().map(|(id, atlas)| {
+            let glyph_sequences = self.font_to_sequences.get(id).unwrap();
+            (*id, Self::create_color_buffer(&self.fonts[id], atlas, glyph_sequences, device, screen_width, screen_height))
+        }).collect()
+    }
+
+    fn create_color_buffer(font: &Font, atlas: &ColorAtlas, glyph_sequences: &Vec<GlyphSequence>, device: &wgpu::Device, screen_width: u32, screen_height: u32) -> (wgpu::Buffer, wgpu::Buffer, u32) {
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut indices: Vec<u16> = Vec::new();
+
+        for sequence in glyph_sequences {
+            let scale = sequence.scale;
+            let mut cursor = (sequence.x, sequence.y);
+            for glyph in &sequence.glyphs {
+                if let Some(color_glyph) = atlas.get(glyph.glyph_id, sequence.color) {
+                    let uv = &color_glyph.uv;
+                    let quad = Quad::new(
+                        cursor.0 + (glyph.x_offset + color_glyph.x) * scale,
+                        cursor.1 + (glyph.y_offset + color_glyph.y) * scale,
+                        color_glyph.width * scale,
+                        color_glyph.height * scale,
+                        [uv.u, uv.v],
+                        [uv.u + uv.w, uv.v + uv.h],
+                        screen_width as f32,
+                        screen_height as f32,
+                    );
+                    let quad = Self::synthesize_oblique(font, quad, cursor.1 + glyph.y_offset * scale, screen_width, screen_height);
+                    indices.append(&mut quad.indices((vertices.len()) as u16).to_vec());
+                    vertices.append(&mut quad.vertices().to_vec());
+                }
+                cursor.0 += glyph.x_advance * scale;
+                cursor.1 += glyph.y_advance * scale;
+            }
+        }
+
+        Self::upload(device, &vertices, &indices)
+    }
+
+    fn synthesize_oblique(font: &Font, quad: Quad, baseline: f32, screen_width: u32, screen_height: u32) -> Quad {
+        match font.synthesis.oblique {
+            true => quad.skewed(font::Synthesis::SKEW, baseline, screen_width as f32, screen_height as f32),
+            false => quad,
+        }
+    }
+
+    pub fn create_selection_buffer(&self, device: &wgpu::Device, screen_width: u32, screen_height: u32) -> Option<(wgpu::Buffer, wgpu::Buffer, u32)> {
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut indices: Vec<u16> = Vec::new();
+        for (block, selection) in &self.selections {
+            let Some(layout) = self.layouts.get(*block) else {
+                continue;
+            };
+            for rect in layout.selection_rects(selection.clone()) {
+                let quad = Quad::new(
+                    rect.x_min,
+                    rect.y_min,
+                    rect.x_max - rect.x_min,
+                    rect.y_max - rect.y_min,
+                    [0.0, 0.0],
+                    [0.0, 0.0],
+                    screen_width as f32,
+                    screen_height as f32,
+                )
+                .colored(self.selection_color);
+                indices.append(&mut quad.indices(vertices.len() as u16).to_vec());
+                vertices.append(&mut quad.vertices().to_vec());
+            }
+        }
+        (!indices.is_empty()).then(|| Self::upload(device, &vertices, &indices))
+    }
+
+    fn upload(device: &wgpu::Device, vertices: &[Vertex], indices: &[u16]) -> (wgpu::Buffer, wgpu::Buffer, u32) {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(vertices.as_slice()),
+            contents: bytemuck::cast_slice(vertices),
             usage: wgpu::BufferUsages::VERTEX,
         });
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(indices.as_slice()),
+            contents: bytemuck::cast_slice(indices),
             usage: wgpu::BufferUsages::INDEX,
         });
         let num_indices = indices.len() as u32;
@@ -0,0 +1,804 @@
+use std::{collections::HashMap, ops::Range, sync::Arc};
+
+use unicode_bidi::BidiInfo;
+
+use crate::font::{Font, FontFeatures, FontId};
+
+use super::{
+    attributed::AttributedText,
+    hit::{self, GraphemeBox},
+    layout::{self, Align, Layout},
+    script,
+    shaper::{self, Glyph, Shaper},
+};
+
+#[derive(Debug)]
+pub(crate) struct GlyphSequence {
+    pub glyphs: Vec<Glyph>,
+    /// Pen position of the first glyph, in pixels.
+    pub x: f32,
+    pub y: f32,
+    pub font: Arc<Font>,
+    /// Font units to pixels.
+    pub scale: f32,
+    pub color: [u8; 4],
+    /// Byte range of the text the glyphs were shaped from.
+    pub range: Range<usize>,
+    pub rtl: bool,
+}
+
+impl GlyphSequence {
+    // Width of the sequence in pixels.
+    pub fn advance(&self) -> f32 {
+        self.glyphs.iter().map(|g| g.x_advance).sum::<f32>() * self.scale
+    }
+
+    // Ascender, descender and line gap of the font at the sequence's size.
+    fn metrics(&self) -> Option<(f32, f32, f32)> {
+        let size = self.scale * self.font.face().ok()?.units_per_em() as f32;
+        let metrics = self.font.metrics(size).ok()?;
+        Some((metrics.ascender, metrics.descender, metrics.line_gap))
+    }
+}
+
+// A block of text and everything it is laid out with.
+pub(crate) struct TextBlock {
+    pub x: f32,
+    pub y: f32,
+    pub text: AttributedText,
+    pub fonts: Vec<Arc<Font>>,
+    pub features: FontFeatures,
+    pub layout: Layout,
+}
+
+// A laid out line of a block.
+pub(crate) struct Line {
+    /// Byte range of the block's text, including trailing whitespace.
+    pub range: Range<usize>,
+    /// In visual order, positioned on the baseline.
+    pub sequences: Vec<GlyphSequence>,
+    /// Left edge of the glyphs, trailing whitespace of a right-to-left line hangs past it.
+    pub x: f32,
+    /// Width without trailing whitespace.
+    pub width: f32,
+    pub baseline: f32,
+    pub ascender: f32,
+    pub descender: f32,
+    pub line_gap: f32,
+}
+
+// What a span of a block is shaped with.
+struct RunStyle<'a> {
+    fonts: &'a [Arc<Font>],
+    /// Pixels per em.
+    size: f32,
+    color: [u8; 4],
+    features: Vec<harfbuzz::sys::hb_feature_t>,
+    script: Option<[u8; 4]>,
+    language: Option<&'a str>,
+}
+
+/// Size of a block of text as [`Typesetter::typeset`] lays it out, in pixels. Positions are
+/// relative to the block's origin, y grows upwards.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextMetrics {
+    /// Width of the widest line, without trailing whitespace.
+    pub width: f32,
+    /// From the ascender of the first line to the descender of the last.
+    pub height: f32,
+    /// Union of the glyph outlines, `None` if there is no ink.
+    pub ink_bounds: Option<Bounds>,
+    pub lines: Vec<LineMetrics>,
+}
+
+impl TextMetrics {
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+/// A line of [`TextMetrics`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineMetrics {
+    /// Byte range of the text, including trailing whitespace.
+    pub range: Range<usize>,
+    /// Left edge of the line, trailing whitespace of a right-to-left line hangs past it.
+    pub x: f32,
+    /// Advance width without trailing whitespace.
+    pub width: f32,
+    pub baseline: f32,
+    /// Of the tallest span on the line.
+    pub ascender: f32,
+    /// Negative below the baseline.
+    pub descender: f32,
+    pub line_gap: f32,
+}
+
+/// A rectangle in pixels, y grows upwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub x_min: f32,
+    pub y_min: f32,
+    pub x_max: f32,
+    pub y_max: f32,
+}
+
+impl Bounds {
+    fn union(self, other: Bounds) -> Bounds {
+        Bounds {
+            x_min: self.x_min.min(other.x_min),
+            y_min: self.y_min.min(other.y_min),
+            x_max: self.x_max.max(other.x_max),
+            y_max: self.y_max.max(other.y_max),
+        }
+    }
+}
+
+/// A laid out block of text, for measuring and hit testing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    text: String,
+    pub(crate) lines: Vec<LineMetrics>,
+    /// Per line, in visual order.
+    pub(crate) graphemes: Vec<Vec<GraphemeBox>>,
+    ink_bounds: Option<Bounds>,
+}
+
+impl TextLayout {
+    pub(crate) fn new(text: &str, lines: &[Line]) -> Self {
+        Self {
+            text: text.to_owned(),
+            lines: lines
+                .iter()
+                .map(|line| LineMetrics {
+                    range: line.range.clone(),
+                    x: line.x,
+                    width: line.width,
+                    baseline: line.baseline,
+                    ascender: line.ascender,
+                    descender: line.descender,
+                    line_gap: line.line_gap,
+                })
+                .collect(),
+            graphemes: lines.iter().map(|line| hit::graphemes(text, line)).collect(),
+            ink_bounds: lines
+                .iter()
+                .flat_map(|line| &line.sequences)
+                .filter_map(Self::ink_bounds)
+                .reduce(Bounds::union),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn lines(&self) -> &[LineMetrics] {
+        &self.lines
+    }
+
+    pub fn metrics(&self) -> TextMetrics {
+        let height = match (self.lines.first(), self.lines.last()) {
+            (Some(first), Some(last)) => first.baseline + first.ascender - (last.baseline + last.descender),
+            _ => 0.0,
+        };
+        TextMetrics {
+            width: self.lines.iter().map(|line| line.width).fold(0.0, f32::max),
+            height,
+            ink_bounds: self.ink_bounds,
+            lines: self.lines.clone(),
+        }
+    }
+
+    fn ink_bounds(sequence: &GlyphSequence) -> Option<Bounds> {
+        let face = sequence.font.face().ok()?;
+        let mut cursor = (sequence.x, sequence.y);
+        let mut bounds: Option<Bounds> = None;
+        for glyph in &sequence.glyphs {
+            if let Some(rect) = face.glyph_bounding_box(glyph.glyph_id) {
+                let x = cursor.0 + glyph.x_offset * sequence.scale;
+                let y = cursor.1 + glyph.y_offset * sequence.scale;
+                let glyph_bounds = Bounds {
+                    x_min: x + rect.x_min as f32 * sequence.scale,
+                    y_min: y + rect.y_min as f32 * sequence.scale,
+                    x_max: x + rect.x_max as f32 * sequence.scale,
+                    y_max: y + rect.y_max as f32 * sequence.scale,
+                };
+                bounds = Some(bounds.map_or(glyph_bounds, |bounds| bounds.union(glyph_bounds)));
+            }
+            cursor.0 += glyph.x_advance * sequence.scale;
+            cursor.1 += glyph.y_advance * sequence.scale;
+        }
+        bounds
+    }
+}
+
+/// Shapes and lays out text without a GPU, [`crate::font::Font`]s are shaped with a cached
+/// harfbuzz font each.
+pub struct Typesetter {
+    shapers: HashMap<FontId, Shaper>,
+    scale_factor: f64,
+    viewport_width: Option<f32>,
+}
+
+impl Typesetter {
+    /// `scale_factor` converts the logical lengths of a block, its position, font sizes and the
+    /// width and height of its [`Layout`], to physical pixels. Laid out text is in physical pixels.
+    pub fn new(scale_factor: f64) -> Self {
+        Self {
+            shapers: HashMap::new(),
+            scale_factor,
+            viewport_width: None,
+        }
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    pub fn viewport_width(&self) -> Option<f32> {
+        self.viewport_width
+    }
+
+    /// The width in physical pixels blocks without a [`Layout::max_width`] are wrapped in, at its
+    /// right edge. Without a viewport, the default, such blocks are only broken at mandatory breaks.
+    pub fn set_viewport_width(&mut self, viewport_width: Option<f32>) {
+        self.viewport_width = viewport_width;
+    }
+
+    /// Lays `text` out as it would be drawn at the origin with `layout`.
+    pub fn typeset(&mut self, text: &AttributedText, fonts: &[Arc<Font>], features: &FontFeatures, layout: &Layout) -> TextLayout {
+        let block = TextBlock {
+            x: 0.0,
+            y: 0.0,
+            text: text.clone(),
+            fonts: fonts.to_vec(),
+            features: features.clone(),
+            layout: layout.clone(),
+        };
+        let lines = self.lay_out(&block);
+        TextLayout::new(text.text(), &lines)
+    }
+
+    /// The size of `text` as [`Typesetter::typeset`] lays it out.
+    pub fn measure(&mut self, text: &AttributedText, fonts: &[Arc<Font>], features: &FontFeatures, layout: &Layout) -> TextMetrics {
+        self.typeset(text, fonts, features, layout).metrics()
+    }
+
+    // Breaks `block` into lines and positions their glyphs in physical pixels.
+    //TODO: Cache
+    pub(crate) fn lay_out(&mut self, block: &TextBlock) -> Vec<Line> {
+        let TextBlock { fonts, layout, .. } = block;
+        let Some(primary) = fonts.first() else {
+            return Vec::new();
+        };
+        let scale = self.scale_factor as f32;
+        let (x, y) = (block.x * scale, block.y * scale);
+        let max_width = match (layout.max_width, self.viewport_width) {
+            (Some(max_width), _) => max_width * scale,
+            (None, Some(viewport_width)) => viewport_width - x,
+            (None, None) => f32::INFINITY,
+        };
+        let size = layout.size.to_physical(self.scale_factor);
+        let spans = block.text.runs();
+        let styles = spans
+            .iter()
+            .map(|(range, attributes)| {
+                let features = attributes
+                    .features
+                    .features()
+                    .iter()
+                    .fold(block.features.clone(), |features, feature| features.with_feature(feature.clone()));
+                let style = RunStyle {
+                    fonts: attributes.fonts.as_deref().unwrap_or(fonts),
+                    size: attributes.size.map_or(size, |size| size.to_physical(self.scale_factor)),
+                    color: attributes.color.unwrap_or(Self::FOREGROUND),
+                    features: Self::hb_features(&features),
+                    script: layout.script,
+                    language: layout.language.as_deref(),
+                };
+                (range.clone(), style)
+            })
+            .collect::<Vec<_>>();
+        let text = block.text.text();
+
+        // Every paragraph is broken first, vertical alignment depends on the number of lines.
+        let bidi = BidiInfo::new(text, None);
+        let mut lines = Vec::new();
+        for paragraph in &bidi.paragraphs {
+            // The paragraph separator isn't drawn.
+            let content = text[paragraph.range.clone()].trim_end_matches(['\n', '\r', '\u{85}', '\u{2029}']);
+            let range = paragraph.range.start..paragraph.range.start + content.len();
+
+            // Lines are measured on the text shaped in logical order.
+            let mut advances = vec![0.0; range.len()];
+            let mut start = range.start;
+            while start < range.end {
+                let level = bidi.levels[start];
+                let end = (start..range.end).find(|i| bidi.levels[*i] != level).unwrap_or(range.end);
+                for sequence in self.shape_styled(text, start..end, level.is_rtl(), &styles) {
+                    for glyph in &sequence.glyphs {
+                        advances[glyph.cluster - range.start] += glyph.x_advance * sequence.scale;
+                    }
+                }
+                start = end;
+            }
+            let breaks = layout::break_lines(text, range.clone(), max_width, |line| {
+                advances[line.start - range.start..line.end - range.start].iter().sum()
+            });
+            // A line ending in a forced break is aligned like the last line of a paragraph.
+            let count = breaks.len();
+            lines.extend(breaks.into_iter().enumerate().map(|(index, line)| {
+                let last = index + 1 == count || layout::ends_in_break(&text[line.clone()]);
+                (Some(paragraph), line, last)
+            }));
+        }
+        // Text ending in a break, or no text at all, ends on an empty line the caret can go to. It
+        // takes the direction of the paragraph before it.
+        if text.is_empty() || layout::ends_in_break(text) {
+            lines.push((bidi.paragraphs.last(), text.len()..text.len(), true));
+        }
+
+        // Lines without glyphs take the metrics of the primary font.
+        let empty_line = primary
+            .metrics(size)
+            .map(|metrics| (metrics.ascender, metrics.descender, metrics.line_gap))
+            .unwrap_or_default();
+        let mut shaped = Vec::with_capacity(lines.len());
+        for (paragraph, line, last) in lines {
+            let rtl = paragraph.is_some_and(|paragraph| paragraph.level.is_rtl());
+            let mut sequences = Vec::new();
+            let mut width = 0.0;
+            let mut start = match max_width.is_finite() {
+                true => x + layout.align.offset(rtl, max_width),
+                false => x,
+            };
+            if let Some(paragraph) = paragraph.filter(|_| !line.is_empty()) {
+                let (levels, runs) = bidi.visual_runs(paragraph, line.clone());
+                for run in runs {
+                    sequences.extend(self.shape_styled(text, run.clone(), levels[run.start].is_rtl(), &styles));
+                }
+                let (offset, visible) = Self::align(text, line.clone(), &mut sequences, layout, rtl, last, max_width);
+                start = x + offset;
+                let mut cursor = start;
+                for sequence in &mut sequences {
+                    sequence.x = cursor;
+                    cursor += sequence.advance();
+                }
+                width = visible;
+            }
+            // The tallest span of a line decides its height.
+            let metrics = sequences
+                .iter()
+                .filter_map(GlyphSequence::metrics)
+                .reduce(|a, b| (a.0.max(b.0), a.1.min(b.1), a.2.max(b.2)))
+                .unwrap_or(empty_line);
+            shaped.push((line, sequences, start, width, metrics));
+        }
+
+        // Baselines are apart by the descent and gap of the line above plus the ascent of the line below.
+        let advances = shaped
+            .windows(2)
+            .map(|pair| {
+                let (_, _, _, _, (_, descender, line_gap)) = pair[0];
+                let (_, _, _, _, (ascender, _, _)) = pair[1];
+                line_gap - descender + ascender
+            })
+            .collect::<Vec<_>>();
+        let (Some((_, _, _, _, (ascender, _, _))), Some((_, _, _, _, (_, descender, _)))) = (shaped.first(), shaped.last()) else {
+            return Vec::new();
+        };
+        let text_height = ascender + advances.iter().sum::<f32>() - descender;
+        let mut baseline = layout.first_baseline(y, *ascender, text_height, scale);
+        shaped
+            .into_iter()
+            .zip(std::iter::once(0.0).chain(advances))
+            .map(|((range, mut sequences, x, width, (ascender, descender, line_gap)), advance)| {
+                // Screen y grows upwards.
+                baseline -= advance;
+                for sequence in &mut sequences {
+                    sequence.y = baseline;
+                }
+                Line {
+                    range,
+                    sequences,
+                    x,
+                    width,
+                    baseline,
+                    ascender,
+                    descender,
+                    line_gap,
+                }
+            })
+            .collect()
+    }
+
+    // Justifies the shaped `line` if asked to, unless it is `last` of its paragraph or ends in a forced
+    // break. Returns where it starts relative to the block and its width without trailing
+    // whitespace. Lines of unbounded width are aligned to the start.
+    fn align(text: &str, line: Range<usize>, sequences: &mut [GlyphSequence], layout: &Layout, rtl: bool, last: bool, max_width: f32) -> (f32, f32) {
+        // Trailing whitespace hangs past the end of the line and isn't aligned. Being at the
+        // paragraph's end it is on the left of a right-to-left line.
+        let visible_end = line.start + text[line].trim_end().len();
+        let (mut width, trailing) = sequences
+            .iter()
+            .flat_map(|sequence| sequence.glyphs.iter().map(|glyph| (glyph, glyph.x_advance * sequence.scale)))
+            .fold((0.0, 0.0), |(width, trailing), (glyph, advance)| match glyph.cluster < visible_end {
+                true => (width + advance, trailing),
+                false => (width, trailing + advance),
+            });
+
+        if !max_width.is_finite() {
+            return (if rtl { -trailing } else { 0.0 }, width);
+        }
+        if layout.align == Align::Justify && !last && width < max_width {
+            let mut gaps = sequences
+                .iter_mut()
+                .flat_map(|sequence| sequence.glyphs.iter_mut().map(|glyph| (glyph, sequence.scale)))
+                .filter(|(glyph, _)| glyph.cluster < visible_end && glyph.x_advance != 0.0)
+                .collect::<Vec<_>>();
+            if layout.inter_character {
+                // No space after the rightmost glyph.
+                gaps.pop();
+            } else {
+                gaps.retain(|(glyph, _)| text[glyph.cluster..].starts_with(char::is_whitespace));
+            }
+            if !gaps.is_empty() {
+                let extra = (max_width - width) / gaps.len() as f32;
+                for (glyph, scale) in gaps {
+                    glyph.x_advance += extra / scale;
+                }
+                width = max_width;
+            }
+        }
+
+        let offset = layout.align.offset(rtl, max_width - width);
+        match rtl {
+            true => (offset - trailing, width),
+            false => (offset, width),
+        }
+    }
+
+    // Shapes a run of one direction span by span, the pieces come back in visual order.
+    fn shape_styled(&mut self, text: &str, run: Range<usize>, rtl: bool, styles: &[(Range<usize>, RunStyle)]) -> Vec<GlyphSequence> {
+        let mut pieces = Vec::new();
+        for (range, style) in styles {
+            let piece = range.start.max(run.start)..range.end.min(run.end);
+            if !piece.is_empty() {
+                pieces.push(self.shape_run(text, piece, rtl, style));
+            }
+        }
+        if rtl {
+            pieces.reverse();
+        }
+        pieces.into_iter().flatten().collect()
+    }
+
+    // Shapes a run of one direction, split by script and then by fallback font. The pieces come
+    // back in visual order.
+    fn shape_run(&mut self, text: &str, run: Range<usize>, rtl: bool, style: &RunStyle) -> Vec<GlyphSequence> {
+        let direction = if rtl { harfbuzz::Direction::RTL } else { harfbuzz::Direction::LTR };
+        let items = match style.script {
+            Some(script) => vec![(run, script)],
+            None => script::itemize(text, run),
+        };
+        let mut sequences = Vec::new();
+        for (item, script) in items {
+            for (range, font) in Self::segment(&text[item.clone()], style.fonts) {
+                let range = item.start + range.start..item.start + range.end;
+                let buffer = shaper::buffer(text, range.clone(), direction, script, style.language);
+                let shaper = self.shapers.entry(font.id()).or_insert_with(|| Shaper::new(&font));
+                let glyphs = shaper.shape(buffer, &style.features, style.size);
+                sequences.push(GlyphSequence {
+                    glyphs,
+                    x: 0.0,
+                    y: 0.0,
+                    scale: style.size / shaper.units_per_em() as f32,
+                    color: style.color,
+                    range,
+                    rtl,
+                    font,
+                });
+            }
+        }
+        if rtl {
+            sequences.reverse();
+        }
+        sequences
+    }
+
+    // Splits `text` into runs of characters covered by the same font. Whitespace sticks to the
+    // current run and characters no font covers fall back to the primary font, which shapes them
+    // to its .notdef glyph. That is drawn if it has an outline and skipped otherwise.
+    fn segment(text: &str, fonts: &[Arc<Font>]) -> Vec<(Range<usize>, Arc<Font>)> {
+        if fonts.is_empty() {
+            return Vec::new();
+        }
+        let faces = fonts.iter().map(|font| font.face().ok()).collect::<Vec<_>>();
+        let covers = |index: usize, c: char| {
+            faces[index]
+                .as_ref()
+                .map(|face| face.glyph_index(c).is_some())
+                .unwrap_or(false)
+        };
+
+        let mut segments: Vec<(Range<usize>, usize)> = Vec::new();
+        for (offset, c) in text.char_indices() {
+            let end = offset + c.len_utf8();
+            let current = segments.last().map(|(_, index)| *index);
+            let index = match current {
+                Some(current) if c.is_whitespace() && covers(current, c) => current,
+                _ => (0..fonts.len()).find(|index| covers(*index, c)).unwrap_or(0),
+            };
+            match segments.last_mut() {
+                Some((range, current)) if *current == index => range.end = end,
+                _ => segments.push((offset..end, index)),
+            }
+        }
+
+        segments
+            .into_iter()
+            .map(|(range, index)| (range, fonts[index].clone()))
+            .collect()
+    }
+
+    // Text without a color attribute.
+    const FOREGROUND: [u8; 4] = [0, 0, 0, 255];
+
+    // Buffers keep the whole text as context, so clusters and feature ranges are byte offsets into it.
+    fn hb_features(features: &FontFeatures) -> Vec<harfbuzz::sys::hb_feature_t> {
+        features
+            .features()
+            .iter()
+            .map(|feature| {
+                let (start, end) = match &feature.range {
+                    None => (0, u32::MAX),
+                    Some(bytes) => (bytes.start as u32, bytes.end as u32),
+                };
+                harfbuzz::sys::hb_feature_t {
+                    tag: u32::from_be_bytes(feature.tag),
+                    value: feature.value,
+                    start,
+                    end,
+                }
+            })
+            .collect()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::{Feature, FontSize};
+
+    const ROBOTO: &[u8] = include_bytes!("../fonts/Roboto/Roboto-Regular.ttf");
+
+    // At 2048 pixels per em a font unit of Roboto is one pixel.
+    fn roboto() -> Arc<Font> {
+        Arc::new(Font::from_data(ROBOTO, Default::default()).unwrap())
+    }
+
+    fn layout() -> Layout {
+        Layout::new().with_size(FontSize::Pixels(2048.0))
+    }
+
+    fn typeset(text: &str, layout: &Layout) -> TextLayout {
+        Typesetter::new(1.0).typeset(&text.into(), &[roboto()], &FontFeatures::default(), layout)
+    }
+
+    fn glyph(c: char) -> (u16, Option<ttf_parser::Rect>) {
+        let face = ttf_parser::Face::parse(ROBOTO, 0).unwrap();
+        let id = face.glyph_index(c).unwrap();
+        (face.glyph_hor_advance(id).unwrap(), face.glyph_bounding_box(id))
+    }
+
+    #[test]
+    fn width_is_the_sum_of_advances() {
+        let (advance, _) = glyph('m');
+        let metrics = typeset("mmm", &layout()).metrics();
+        assert_eq!(metrics.width, 3.0 * advance as f32);
+        assert_eq!(metrics.line_count(), 1);
+    }
+
+    #[test]
+    fn lines_wrap_at_the_max_width() {
+        let (advance, _) = glyph('m');
+        let layout = layout().with_max_width(7.5 * advance as f32);
+        let metrics = typeset("mmm mmm mmm", &layout).metrics();
+        assert_eq!(metrics.lines.iter().map(|line| line.range.clone()).collect::<Vec<_>>(), [0..8, 8..11]);
+        assert_eq!(metrics.width, 6.0 * advance as f32 + glyph(' ').0 as f32);
+    }
+
+    #[test]
+    fn ink_bounds_are_the_glyph_outlines() {
+        let (advance, Some(bounds)) = glyph('m') else {
+            panic!("m has no outline");
+        };
+        let ink = typeset("mm", &layout()).metrics().ink_bounds.unwrap();
+        assert_eq!(ink, Bounds {
+            x_min: bounds.x_min as f32,
+            y_min: bounds.y_min as f32,
+            x_max: advance as f32 + bounds.x_max as f32,
+            y_max: bounds.y_max as f32,
+        });
+        assert_eq!(typeset("   ", &layout()).metrics().ink_bounds, None);
+    }
+
+    #[test]
+    fn lines_take_the_font_metrics() {
+        let font = roboto().metrics(2048.0).unwrap();
+        let metrics = typeset("m\nm", &layout()).metrics();
+        let baselines = metrics.lines.iter().map(|line| line.baseline).collect::<Vec<_>>();
+        assert_eq!(baselines, [0.0, -font.line_height()]);
+        for line in &metrics.lines {
+            assert_eq!((line.ascender, line.descender, line.line_gap), (font.ascender, font.descender, font.line_gap));
+        }
+        assert_eq!(metrics.height, font.ascender - (-font.line_height() + font.descender));
+    }
+
+    #[test]
+    fn empty_text_has_one_empty_line() {
+        let font = roboto().metrics(2048.0).unwrap();
+        let layout = typeset("", &layout());
+        let metrics = layout.metrics();
+        assert_eq!(metrics.lines.iter().map(|line| line.range.clone()).collect::<Vec<_>>(), vec![0..0]);
+        assert_eq!((metrics.width, metrics.height, metrics.ink_bounds), (0.0, font.ascender - font.descender, None));
+        assert_eq!(layout.caret(0).map(|caret| caret.x_min), Some(0.0));
+    }
+
+    #[test]
+    fn a_trailing_newline_ends_on_an_empty_line() {
+        let font = roboto().metrics(2048.0).unwrap();
+        let layout = typeset("m\n", &layout());
+        assert_eq!(layout.lines().iter().map(|line| line.range.clone()).collect::<Vec<_>>(), [0..1, 2..2]);
+        let caret = layout.caret(2).unwrap();
+        assert_eq!((caret.x_min, caret.y_max), (0.0, -font.line_height() + font.ascender));
+        assert_eq!(layout.hit_test(0.0, -font.line_height()), 2);
+    }
+
+    // "m m m m" wrapped after the third m, with half an m of room left on the first line.
+    fn justified(inter_character: bool) -> (TextLayout, f32, f32, f32) {
+        let (m, s) = (glyph('m').0 as f32, glyph(' ').0 as f32);
+        let max_width = 3.5 * m + 2.0 * s;
+        let layout = layout()
+            .with_max_width(max_width)
+            .with_align(Align::Justify)
+            .with_inter_character(inter_character);
+        (typeset("m m m m", &layout), m, s, max_width)
+    }
+
+    fn caret_x(layout: &TextLayout, index: usize) -> f32 {
+        layout.caret(index).unwrap().x_min
+    }
+
+    #[test]
+    fn justify_stretches_the_spaces_between_words() {
+        let (layout, m, s, max_width) = justified(false);
+        assert_eq!(layout.lines().iter().map(|line| line.range.clone()).collect::<Vec<_>>(), [0..6, 6..7]);
+        // Two gaps share the half m, the trailing space isn't stretched and hangs past the edge.
+        assert_eq!(layout.lines()[0].width, max_width);
+        assert_eq!([2, 4, 5].map(|index| caret_x(&layout, index)), [m + s + 0.25 * m, 2.0 * m + 2.0 * s + 0.5 * m, max_width]);
+        assert_eq!(caret_x(&layout, 6), 0.0);
+        // The last line keeps its width.
+        assert_eq!(layout.lines()[1].width, m);
+    }
+
+    #[test]
+    fn inter_character_justify_stretches_every_glyph() {
+        let (layout, m, s, max_width) = justified(true);
+        // Five visible glyphs on the first line, four gaps.
+        let extra = 0.5 * m / 4.0;
+        assert_eq!(layout.lines()[0].width, max_width);
+        assert_eq!([1, 2].map(|index| caret_x(&layout, index)), [m + extra, m + s + 2.0 * extra]);
+    }
+
+    #[test]
+    fn lines_ending_in_a_forced_break_are_not_justified() {
+        let (m, s) = (glyph('m').0 as f32, glyph(' ').0 as f32);
+        let layout = layout().with_max_width(10.0 * m).with_align(Align::Justify);
+        let widths = typeset("m m\u{2028}m m m", &layout).lines().iter().map(|line| line.width).collect::<Vec<_>>();
+        assert_eq!(widths, [2.0 * m + s, 3.0 * m + 2.0 * s]);
+    }
+
+    fn cairo() -> Arc<Font> {
+        Arc::new(Font::from_data(include_bytes!("../fonts/Cairo/static/Cairo-Regular.ttf") as &[u8], Default::default()).unwrap())
+    }
+
+    #[test]
+    fn right_to_left_lines_start_on_the_right() {
+        let cairo = cairo();
+        let line = |align| {
+            let layout = layout().with_max_width(20000.0).with_align(align);
+            let layout = Typesetter::new(1.0).typeset(&"مرحبا".into(), std::slice::from_ref(&cairo), &FontFeatures::default(), &layout);
+            layout.lines()[0].clone()
+        };
+        let start = line(Align::Start);
+        assert_eq!(start.x + start.width, 20000.0);
+        assert_eq!(line(Align::End).x, 0.0);
+    }
+
+    // The sequences of a one line block as (text, font, right to left, x), in visual order.
+    fn sequences(text: &str) -> Vec<(&str, FontId, bool, f32)> {
+        let block = TextBlock {
+            x: 0.0,
+            y: 0.0,
+            text: text.into(),
+            fonts: vec![roboto(), cairo()],
+            features: FontFeatures::default(),
+            layout: layout(),
+        };
+        let lines = Typesetter::new(1.0).lay_out(&block);
+        assert_eq!(lines.len(), 1);
+        let sequences = &lines[0].sequences;
+        // Sequences follow each other without gaps.
+        for pair in sequences.windows(2) {
+            assert_eq!(pair[0].x + pair[0].advance(), pair[1].x);
+        }
+        sequences
+            .iter()
+            .map(|sequence| {
+                // Glyphs are in visual order, backwards through the text of a right-to-left run.
+                let clusters = sequence.glyphs.iter().map(|glyph| glyph.cluster).collect::<Vec<_>>();
+                let mut logical = clusters.clone();
+                logical.sort_unstable();
+                if sequence.rtl {
+                    logical.reverse();
+                }
+                assert_eq!(clusters, logical);
+                (&text[sequence.range.clone()], sequence.font.id(), sequence.rtl, sequence.x)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn runs_are_reordered_visually() {
+        let (roboto, cairo) = (roboto().id(), cairo().id());
+        let ltr = sequences("abc مرحبا def");
+        assert_eq!(
+            ltr.iter().map(|(text, font, rtl, _)| (*text, *font, *rtl)).collect::<Vec<_>>(),
+            [("abc ", roboto, false), ("مرحبا", cairo, true), (" def", roboto, false)]
+        );
+        assert_eq!(ltr[0].3, 0.0);
+
+        // In a right-to-left paragraph the embedded Latin word comes first from the left.
+        let rtl = sequences("مرحبا abc");
+        assert_eq!(
+            rtl.iter().map(|(text, font, rtl, _)| (*text, *font, *rtl)).collect::<Vec<_>>(),
+            [("abc", roboto, false), ("مرحبا ", cairo, true)]
+        );
+        assert_eq!(rtl[0].3, 0.0);
+    }
+
+    #[test]
+    fn feature_ranges_become_cluster_ranges() {
+        let features = FontFeatures::new()
+            .with_kerning(false)
+            .with_range(*b"ss01", 2, 3..8);
+        let features = Typesetter::hb_features(&features)
+            .into_iter()
+            .map(|feature| (feature.tag, feature.value, feature.start, feature.end))
+            .collect::<Vec<_>>();
+        assert_eq!(features, [(0x6b65726e, 0, 0, u32::MAX), (0x73733031, 2, 3, 8)]);
+    }
+
+    #[test]
+    fn ranged_features_only_shape_their_bytes() {
+        let block = TextBlock {
+            x: 0.0,
+            y: 0.0,
+            text: "fi fi".into(),
+            fonts: vec![roboto()],
+            features: FontFeatures::new().with_range(Feature::STANDARD_LIGATURES, 0, 3..5),
+            layout: layout(),
+        };
+        let lines = Typesetter::new(1.0).lay_out(&block);
+        let clusters = lines[0].sequences[0].glyphs.iter().map(|glyph| glyph.cluster).collect::<Vec<_>>();
+        // The first "fi" becomes a ligature, the second keeps both glyphs.
+        assert_eq!(clusters, [0, 2, 3, 4]);
+    }
+}